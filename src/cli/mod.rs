@@ -0,0 +1,402 @@
+//! Command line interface of the planner binary
+//!
+//! ```text
+//! planner plan --recipes factory.txt --target 重型模块化框架 --rate 4 --source 铁锭
+//! planner items --recipes factory.txt
+//! planner validate --recipes factory.txt
+//! planner tree --recipes factory.txt --target 重型模块化框架
+//! ```
+
+#[cfg(test)]
+mod test;
+
+use std::{collections::HashMap, io::Write, rc::Rc};
+
+use planner::{
+    calculate_many, max_rate, maximize, report::suggest, trace::Stderr, FormulaList, Fraction, Item, Objective, Options, PlanResult, RateUnit, RecipeSelection, Report, Selected, Solver, Trace,
+};
+
+pub const USAGE: &str = "usage: planner <command> --recipes <file> [options]
+
+commands:
+  plan      calculate the plan of a target
+  max       most of a target per minute the supply and power allow,
+            with its plan if items are supplied
+  items     list all items
+  validate  check the recipe file
+  tree      show the recipe tree of a target
+
+options:
+  --recipes <file>       recipe file, or the Docs.json of the game if built with docs
+  --target <item>        item to produce, for plan, max and tree
+  --target <item>=<n>    another item to produce at n per minute, for plan, can repeat
+  --rate <n>             target items per minute, for plan
+  --source <item>        item supplied from outside, can repeat
+  --supply <item>=<n>    item supplied from outside at n per minute, for max, can repeat
+  --recipe <item>=<n>    use recipe n of the item, can repeat
+  --cheapest             use the recipes consuming the least raw items
+  --solver <solver>      graph, raw or buildings, defaults to graph
+  --power <mw>           maximum total power in MW, for plan
+  --overclock <percent>  run buildings at the same clock up to percent, for plan
+  --trace                print the steps of the graph solver to stderr, for plan and max
+  --unit <unit>          show plans per s, min or h, defaults to min, for plan and max";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    Plan(Args),
+    Max(Args),
+    Items(Args),
+    Validate(Args),
+    Tree(Args),
+    Help,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Args {
+    recipes: String,
+    target: Option<String>,
+    targets: Vec<(String, u32)>,
+    rate: Option<u32>,
+    source: Vec<String>,
+    supply: Vec<(String, u32)>,
+    recipe: Vec<(String, usize)>,
+    cheapest: bool,
+    solver: Solver,
+    power: Option<u32>,
+    overclock: Option<u32>,
+    trace: bool,
+    unit: RateUnit,
+}
+
+impl Command {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+        let mut args = args.into_iter();
+        let command = args.next().ok_or("missing command")?;
+        if !matches!(
+            command.as_str(),
+            "plan" | "max" | "items" | "validate" | "tree" | "help" | "-h" | "--help"
+        ) {
+            return Err(format!("unknown command {command}"));
+        }
+        let mut a = Args::default();
+        let mut recipes = None;
+        while let Some(flag) = args.next() {
+            let mut value = || args.next().ok_or(format!("missing value of {flag}"));
+            match flag.as_str() {
+                "--recipes" => recipes = Some(value()?),
+                "--target" => {
+                    let v = value()?;
+                    if !v.contains('=') {
+                        a.target = Some(v);
+                        continue;
+                    }
+                    let target = v
+                        .split_once('=')
+                        .and_then(|(item, rate)| Some((item.to_string(), rate.parse().ok()?)))
+                        .ok_or(format!("invalid target {v}, expect <item>=<n>"))?;
+                    a.targets.push(target);
+                }
+                "--rate" => {
+                    let v = value()?;
+                    let rate = v.parse().map_err(|_| format!("invalid rate {v}"))?;
+                    a.rate = Some(rate);
+                }
+                "--source" => a.source.push(value()?),
+                "--supply" => {
+                    let v = value()?;
+                    let supply = v
+                        .split_once('=')
+                        .and_then(|(item, rate)| Some((item.to_string(), rate.parse().ok()?)))
+                        .ok_or(format!("invalid supply {v}, expect <item>=<n>"))?;
+                    a.supply.push(supply);
+                }
+                "--recipe" => {
+                    let v = value()?;
+                    let recipe = v
+                        .split_once('=')
+                        .and_then(|(item, index)| Some((item.to_string(), index.parse().ok()?)))
+                        .ok_or(format!("invalid recipe {v}, expect <item>=<n>"))?;
+                    a.recipe.push(recipe);
+                }
+                "--cheapest" => a.cheapest = true,
+                "--trace" => a.trace = true,
+                "--solver" => {
+                    a.solver = match value()?.as_str() {
+                        "graph" => Solver::Graph,
+                        "raw" => Solver::Linear(Objective::RawResource),
+                        "buildings" => Solver::Linear(Objective::Buildings),
+                        v => return Err(format!("unknown solver {v}")),
+                    }
+                }
+                "--power" => {
+                    let v = value()?;
+                    let power = v.parse().map_err(|_| format!("invalid power {v}"))?;
+                    a.power = Some(power);
+                }
+                "--overclock" => {
+                    let v = value()?;
+                    let clock = v.parse().map_err(|_| format!("invalid overclock {v}"))?;
+                    if clock == 0 {
+                        return Err(format!("invalid overclock {v}"));
+                    }
+                    a.overclock = Some(clock);
+                }
+                "--unit" => {
+                    a.unit = match value()?.as_str() {
+                        "s" => RateUnit::Second,
+                        "min" => RateUnit::Minute,
+                        "h" => RateUnit::Hour,
+                        v => return Err(format!("unknown unit {v}")),
+                    }
+                }
+                "-h" | "--help" => return Ok(Command::Help),
+                _ => return Err(format!("unknown option {flag}")),
+            }
+        }
+
+        if matches!(command.as_str(), "help" | "-h" | "--help") {
+            return Ok(Command::Help);
+        }
+        a.recipes = recipes.ok_or("missing --recipes")?;
+        let command = match command.as_str() {
+            "plan" => {
+                if a.targets.is_empty() || a.target.is_some() {
+                    a.target.as_ref().ok_or("missing --target")?;
+                    a.rate.ok_or("missing --rate")?;
+                }
+                Command::Plan(a)
+            }
+            "max" => {
+                a.target.as_ref().ok_or("missing --target")?;
+                // the most a target can be made at is found at 100% clock only
+                if a.overclock.is_some() {
+                    return Err("--overclock is only for plan".to_string());
+                }
+                Command::Max(a)
+            }
+            "items" => Command::Items(a),
+            "validate" => Command::Validate(a),
+            "tree" => {
+                a.target.as_ref().ok_or("missing --target")?;
+                Command::Tree(a)
+            }
+            _ => unreachable!(),
+        };
+        Ok(command)
+    }
+
+    /// Write the results to `out`, and the errors of the recipe file to `err` unless
+    /// validating, where they are the results.
+    pub fn run(&self, out: &mut impl Write, err: &mut impl Write) -> Result<(), String> {
+        match self {
+            Command::Help => writeln!(out, "{USAGE}").map_err(|e| e.to_string()),
+            Command::Plan(a) => {
+                let formula_list = a.load_all(err, false)?;
+                let source = a.source(&formula_list)?;
+                let mut targets = Vec::new();
+                if a.target.is_some() {
+                    targets.push((a.target(&formula_list)?, a.rate.unwrap()));
+                }
+                for (item, rate) in a.targets.iter() {
+                    let item = get_item(&formula_list, item)?;
+                    targets.push((item, *rate));
+                }
+                let options = Options {
+                    selection: a.selection(&formula_list)?,
+                    solver: a.solver,
+                    power_limit: a.power,
+                    overclock: a.overclock,
+                    trace: a.trace.then(|| Rc::new(Stderr) as Rc<dyn Trace>),
+                    rate_unit: a.unit,
+                };
+                let plans = calculate_many(&formula_list, &source, &targets, &options)
+                    .map_err(|e| e.to_string())?;
+                let result =
+                    PlanResult::new(&formula_list, &source, plans).map_err(|e| e.to_string())?;
+                for v in result.plans.iter() {
+                    writeln!(out, "{v}").map_err(|e| e.to_string())?;
+                }
+                writeln!(out, "\n{}", result.buildings).map_err(|e| e.to_string())?;
+                if formula_list.transports().is_empty() {
+                    return Ok(());
+                }
+                writeln!(out).map_err(|e| e.to_string())?;
+                for v in result.edges.iter() {
+                    writeln!(out, "{v}").map_err(|e| e.to_string())?;
+                }
+                Ok(())
+            }
+            Command::Max(a) => {
+                let formula_list = a.load_all(err, false)?;
+                let source = a.source(&formula_list)?;
+                let target = a.target(&formula_list)?;
+                let options = Options {
+                    selection: a.selection(&formula_list)?,
+                    solver: a.solver,
+                    power_limit: a.power,
+                    trace: a.trace.then(|| Rc::new(Stderr) as Rc<dyn Trace>),
+                    rate_unit: a.unit,
+                    ..Default::default()
+                };
+                let unit = a.unit;
+                let per_unit = |rate: Fraction| {
+                    rate.checked_mul(unit.minutes()).map_err(|e| e.to_string())
+                };
+                if a.supply.is_empty() {
+                    let rate = max_rate(&formula_list, &source, target, &options)
+                        .map_err(|e| e.to_string())?;
+                    let rate = per_unit(rate)?;
+                    return writeln!(out, "{rate}/{unit}").map_err(|e| e.to_string());
+                }
+
+                let mut supply = Vec::new();
+                for (item, rate) in a.supply.iter() {
+                    let item = get_item(&formula_list, item)?;
+                    supply.push((item, *rate));
+                }
+                let (rate, plans) = maximize(&formula_list, &supply, target, &options)
+                    .map_err(|e| e.to_string())?;
+                let rate = per_unit(rate)?;
+                writeln!(out, "{rate}/{unit}").map_err(|e| e.to_string())?;
+                for v in plans.iter() {
+                    writeln!(out, "{v}").map_err(|e| e.to_string())?;
+                }
+                Ok(())
+            }
+            Command::Items(a) => {
+                let formula_list = a.load_all(err, false)?;
+                for v in formula_list.items() {
+                    let recipes = formula_list.recipes(v).len();
+                    writeln!(out, "{v} recipes={recipes}").map_err(|e| e.to_string())?;
+                }
+                Ok(())
+            }
+            Command::Validate(a) => {
+                let formula_list = a.load_all(out, true)?;
+                let formulas = formula_list.formulas().len();
+                let items = formula_list.items().len();
+                writeln!(out, "ok: {formulas} formulas, {items} items").map_err(|e| e.to_string())
+            }
+            Command::Tree(a) => {
+                let formula_list = a.load_all(err, false)?;
+                let target = a.target(&formula_list)?;
+                let source = a.source(&formula_list)?;
+                let selected = a
+                    .selection(&formula_list)?
+                    .select(&formula_list, &source)
+                    .map_err(|e| e.to_string())?;
+                let mut tree = Tree {
+                    selected: &selected,
+                    source: &source,
+                    path: Vec::new(),
+                };
+                tree.write(out, &target, 0).map_err(|e| e.to_string())
+            }
+        }
+    }
+}
+
+impl Args {
+    fn load(&self) -> Result<FormulaList, String> {
+        #[cfg(feature = "docs")]
+        if self.recipes.ends_with(".json") {
+            let file = std::fs::File::open(&self.recipes).map_err(|e| format!("{}: {e}", self.recipes))?;
+            return FormulaList::from_docs(file).map_err(|e| format!("{}: {e}", self.recipes));
+        }
+        FormulaList::from_path(&self.recipes).map_err(|e| format!("{}: {e}", self.recipes))
+    }
+
+    /// The recipes like [`Args::load`], writing the report of every error of the recipe file
+    /// to `out`, and of the warnings too if `warnings`.
+    fn load_all(&self, out: &mut impl Write, warnings: bool) -> Result<FormulaList, String> {
+        if cfg!(feature = "docs") && self.recipes.ends_with(".json") {
+            return self.load();
+        }
+        let (formula_list, diagnostics) =
+            FormulaList::parse_path(&self.recipes).map_err(|e| format!("{}: {e}", self.recipes))?;
+        for v in diagnostics.iter().filter(|x| warnings || !x.is_warning()) {
+            let report = Report::new(v, &self.recipes, formula_list.items());
+            writeln!(out, "{report}\n").map_err(|e| e.to_string())?;
+        }
+        let errors = diagnostics.iter().filter(|x| !x.is_warning()).count();
+        match errors {
+            0 => Ok(formula_list),
+            1 => Err(format!("{}: 1 error", self.recipes)),
+            n => Err(format!("{}: {n} errors", self.recipes)),
+        }
+    }
+
+    fn target(&self, formula_list: &FormulaList) -> Result<Rc<Item>, String> {
+        let target = self.target.as_deref().unwrap_or_default();
+        get_item(formula_list, target)
+    }
+
+    fn source(&self, formula_list: &FormulaList) -> Result<Vec<Rc<Item>>, String> {
+        self.source
+            .iter()
+            .map(|x| get_item(formula_list, x))
+            .collect()
+    }
+
+    fn selection(&self, formula_list: &FormulaList) -> Result<RecipeSelection, String> {
+        if self.cheapest {
+            return Ok(RecipeSelection::Cheapest);
+        }
+        if self.recipe.is_empty() {
+            return Ok(RecipeSelection::First);
+        }
+        let mut choice = HashMap::new();
+        for (item, index) in self.recipe.iter() {
+            let item = get_item(formula_list, item)?;
+            choice.insert(item, *index);
+        }
+        Ok(RecipeSelection::Explicit(choice))
+    }
+}
+
+/// The item named `name`, suggesting the closest name if there is none.
+fn get_item(formula_list: &FormulaList, name: &str) -> Result<Rc<Item>, String> {
+    formula_list.get_item(name).map_err(|e| match suggest(name, formula_list.items()) {
+        Some(item) => format!("{e}, did you mean `{item}`?"),
+        None => e.to_string(),
+    })
+}
+
+/// Prints the chosen recipe of an item and then its inputs, indented.
+struct Tree<'a> {
+    selected: &'a Selected,
+    source: &'a [Rc<Item>],
+    path: Vec<Rc<Item>>,
+}
+
+impl Tree<'_> {
+    fn write(
+        &mut self,
+        out: &mut impl Write,
+        item: &Rc<Item>,
+        depth: usize,
+    ) -> std::io::Result<()> {
+        let indent = "  ".repeat(depth);
+        if self.source.contains(item) {
+            return writeln!(out, "{indent}{item} (source)");
+        }
+        if self.path.contains(item) {
+            return writeln!(out, "{indent}{item} (loop)");
+        }
+        let Some((recipe, formula)) = self.selected.get(item) else {
+            return writeln!(out, "{indent}{item} (no recipe)");
+        };
+        if *recipe == 0 {
+            writeln!(out, "{indent}{item}: {formula}")?;
+        } else {
+            writeln!(out, "{indent}{item}: [alt {recipe}] {formula}")?;
+        }
+
+        self.path.push(item.clone());
+        for input_i in formula.source() {
+            self.write(out, &input_i.item(), depth + 1)?;
+        }
+        self.path.pop();
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+use super::*;
+
+fn args(v: &str) -> Vec<String> {
+    v.split(' ').map(|x| x.to_string()).collect()
+}
+
+/// A directory of one test, removed with everything in it when dropped.
+struct TempDir(std::path::PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("planner_{name}_{}", std::process::id()));
+        _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        TempDir(dir)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn test_parse() {
+    let command = Command::parse(args(
+        "plan --recipes f.txt --target 加强铁板 --rate 4 --source 铁锭 --source 铁棒 --recipe 铁板=1 --solver raw --power 100 --overclock 250 --trace --unit h",
+    ))
+    .unwrap();
+    let expect = Args {
+        recipes: "f.txt".to_string(),
+        target: Some("加强铁板".to_string()),
+        targets: vec![],
+        rate: Some(4),
+        source: vec!["铁锭".to_string(), "铁棒".to_string()],
+        supply: vec![],
+        recipe: vec![("铁板".to_string(), 1)],
+        cheapest: false,
+        solver: Solver::Linear(Objective::RawResource),
+        power: Some(100),
+        overclock: Some(250),
+        trace: true,
+        unit: RateUnit::Hour,
+    };
+    assert_eq!(command, Command::Plan(expect));
+
+    assert_eq!(Command::parse(args("help")).unwrap(), Command::Help);
+    assert!(Command::parse(args("plan --recipes f.txt --target 加强铁板")).is_err());
+    assert!(Command::parse(args("tree --recipes f.txt --recipe 铁板")).is_err());
+    assert!(Command::parse(args("bogus --recipes f.txt")).is_err());
+    assert!(Command::parse(args("plan --recipes f.txt --overclock 0")).is_err());
+    assert!(Command::parse(args("plan --recipes f.txt --target 铁棒=30 --target 铁锭")).is_err());
+    assert!(Command::parse(args("plan --recipes f.txt --target 铁棒=30")).is_ok());
+    assert!(Command::parse(args("max --recipes f.txt --target 铁棒 --overclock 250")).is_err());
+}
+
+#[test]
+fn test_run() {
+    let dir = TempDir::new("cli_run");
+    let path = dir.0.join("factory.txt");
+    std::fs::write(
+        &path,
+        "铁矿石 1s @矿机\n铁锭 = 铁矿石 2s @冶炼炉\n铁棒 = 铁锭 4s @构筑站\n@构筑站 4MW\n",
+    )
+    .unwrap();
+    let path = path.to_str().unwrap();
+
+    let run = |v: &str| {
+        let mut out = Vec::new();
+        Command::parse(args(&v.replace("FILE", path)))
+            .unwrap()
+            .run(&mut out, &mut std::io::sink())
+            .map(|_| String::from_utf8(out).unwrap())
+    };
+    assert_eq!(
+        run("validate --recipes FILE").unwrap(),
+        "ok: 3 formulas, 3 items\n"
+    );
+    assert_eq!(
+        run("tree --recipes FILE --target 铁棒 --source 铁锭").unwrap(),
+        "铁棒: 铁棒*1 = 铁锭*1 15/min @构筑站\n  铁锭 (source)\n"
+    );
+    let plan = run("plan --recipes FILE --target 铁棒 --rate 30").unwrap();
+    assert_eq!(plan.lines().count(), 9);
+    assert!(plan.ends_with("\n矿机: 1\n冶炼炉: 1\n构筑站: 2\ntotal: 4\npower: 8MW\n"));
+    assert!(run("plan --recipes FILE --target 铁棒 --rate 30 --power 7").is_err());
+    assert_eq!(
+        run("max --recipes FILE --target 铁棒 --power 6").unwrap(),
+        "22.5/min\n"
+    );
+    let plan = run("plan --recipes FILE --target 铁棒=30 --target 铁锭=10").unwrap();
+    assert!(plan.contains("铁锭*40 = 铁矿石*40"));
+    let max = run("max --recipes FILE --target 铁棒 --supply 铁锭=20").unwrap();
+    assert!(max.starts_with("20/min\n"));
+    assert_eq!(max.lines().count(), 3);
+    let max = run("max --recipes FILE --target 铁棒 --supply 铁锭=20 --unit h").unwrap();
+    assert!(max.starts_with("1200/h\n"));
+    assert!(max.contains("speed=1200.00/h"));
+    assert!(run("plan --recipes FILE --target 铁板 --rate 30").is_err());
+}
+
+#[test]
+fn test_validate_errors() {
+    let dir = TempDir::new("cli_validate");
+    let path = dir.0.join("factory.txt");
+    std::fs::write(&path, "铁矿石 1s\n铁锭 = 铁矿石 2x\n铁棒 = 铁锭 4s\n铁板 = 铁锭 \n").unwrap();
+    let path = path.to_str().unwrap();
+
+    let mut out = Vec::new();
+    let command = Command::parse(args(&format!("validate --recipes {path}"))).unwrap();
+    assert_eq!(command.run(&mut out, &mut std::io::sink()), Err(format!("{path}: 2 errors")));
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.starts_with("error: Unknown char 'x' at line 2, col 11"));
+    assert!(out.contains(&format!(" --> {path}:2:11\n")));
+    assert!(out.contains("\n4 | 铁板 = 铁锭 \n"));
+    assert_eq!(out.matches("error: ").count(), 2);
+    assert!(out.contains("warning: Item 铁锭 at line 3 is neither produced nor supplied"));
+
+    // plan reports the errors of the recipe file to stderr, without the warnings
+    std::fs::write(path, "铁矿石 1s\n铁锭 = 铁矿石 2x\n铁棒 = 铁锭 4s\n").unwrap();
+    let (mut out, mut err) = (Vec::new(), Vec::new());
+    let command = Command::parse(args(&format!("plan --recipes {path} --target 铁棒 --rate 10"))).unwrap();
+    assert_eq!(command.run(&mut out, &mut err), Err(format!("{path}: 1 error")));
+    assert!(out.is_empty());
+    let err = String::from_utf8(err).unwrap();
+    assert!(err.starts_with("error: Unknown char 'x' at line 2, col 11"));
+    assert!(!err.contains("warning: "));
+
+    // an unknown item on the command line gets the closest name
+    let path = dir.0.join("suggest.txt");
+    std::fs::write(&path, "铁矿石 1s\n铁锭 = 铁矿石 2s\n铁棒 = 铁锭 4s\n").unwrap();
+    let command = format!("tree --recipes {} --target 铁矿", path.to_str().unwrap());
+    let e = Command::parse(args(&command)).unwrap().run(&mut Vec::new(), &mut Vec::new()).unwrap_err();
+    assert_eq!(e, "Unknown item 铁矿, did you mean `铁矿石`?");
+}
@@ -0,0 +1,230 @@
+//! Import of the `Docs.json` the game ships for community tools
+//!
+//! The file is a UTF-16 JSON array of native classes, each with the `Classes` of that type.
+//! Items come from the descriptor classes, formulas from the `FGRecipe` classes made in a
+//! manufacturer and machines from the `FGBuildableManufacturer` classes, all named by
+//! their display name. Fluids are counted in cubic meters instead of liters. Every resource
+//! consumed by a formula gets a formula without input at 60/min like `铁矿石 1s`, declared
+//! before the other recipes.
+
+#[cfg(test)]
+mod test;
+
+use std::{collections::HashMap, io::Read, rc::Rc};
+
+use serde_json::Value;
+
+use crate::{Error, Formula, FormulaItem, FormulaList, Fraction, Item, Machine, Result};
+
+impl FormulaList {
+    /// Import the `Docs.json` of the game, in UTF-16 with a byte order mark or in UTF-8.
+    pub fn from_docs(mut source: impl Read) -> Result<Self> {
+        let mut bytes = Vec::new();
+        source.read_to_end(&mut bytes)?;
+        let docs: Value = serde_json::from_str(&decode(&bytes)?).map_err(invalid)?;
+        let natives = docs
+            .as_array()
+            .ok_or_else(|| Error::InvalidDocs("expect an array of native classes".to_string()))?;
+
+        let mut items: HashMap<&str, Descriptor> = HashMap::new();
+        let mut machines: HashMap<&str, (Rc<Machine>, Option<Fraction>)> = HashMap::new();
+        let mut recipes: Vec<&Value> = Vec::new();
+        for native in natives {
+            let name = field(native, "NativeClass")?;
+            let classes = native["Classes"]
+                .as_array()
+                .ok_or_else(|| Error::InvalidDocs(format!("{name} has no classes")))?;
+            for class in classes {
+                if name.ends_with(".FGRecipe'") {
+                    recipes.push(class);
+                } else if name.contains(".FGBuildableManufacturer") {
+                    let machine = Rc::new(Machine::new(field(class, "mDisplayName")?.to_string()));
+                    let power = class["mPowerConsumption"]
+                        .as_str()
+                        .and_then(decimal)
+                        .filter(|x| *x != 0.into());
+                    machines.insert(field(class, "ClassName")?, (machine, power));
+                } else if let Some(form) = class["mForm"].as_str() {
+                    let descriptor = Descriptor {
+                        item: Rc::new(Item::new(field(class, "mDisplayName")?.to_string())),
+                        fluid: matches!(form, "RF_LIQUID" | "RF_GAS"),
+                        resource: name.contains(".FGResourceDescriptor'"),
+                    };
+                    items.insert(field(class, "ClassName")?, descriptor);
+                }
+            }
+        }
+        // the standard recipe of an item comes before its alternates
+        recipes.sort_by_key(|x| {
+            x["ClassName"]
+                .as_str()
+                .unwrap_or_default()
+                .starts_with("Recipe_Alternate")
+        });
+
+        let mut formulas = Vec::new();
+        let mut raw: Vec<Rc<Item>> = Vec::new();
+        for recipe in recipes {
+            let Some(formula) = formula(recipe, &items, &machines)? else {
+                continue;
+            };
+            for (class, _) in amounts(field(recipe, "mIngredients")?) {
+                let d = &items[class];
+                if d.resource && !raw.contains(&d.item) {
+                    raw.push(d.item.clone());
+                }
+            }
+            formulas.push(formula);
+        }
+
+        let mut list = FormulaList::new();
+        for item in raw {
+            let formula = Formula::new(
+                vec![FormulaItem::new(item, 1.into())],
+                vec![],
+                60.into(),
+                None,
+            );
+            list.push(formula, 0)?;
+        }
+        for formula in formulas {
+            match list.push(formula, 0) {
+                // the same recipe may be declared for several manufacturers, only an exactly
+                // equal formula is a duplicate and is skipped on purpose
+                Ok(()) | Err(Error::DuplicateRecipe(..)) => {}
+                Err(e) => return Err(Error::InvalidDocs(e.to_string())),
+            }
+        }
+        for (machine, power) in machines.into_values() {
+            if let Some(power) = power {
+                if list.machines().contains(&machine) {
+                    list.set_power(machine, power);
+                }
+            }
+        }
+        Ok(list)
+    }
+}
+
+struct Descriptor {
+    item: Rc<Item>,
+    /// amounts are in liters
+    fluid: bool,
+    /// extracted from a node
+    resource: bool,
+}
+
+/// The formula of `recipe`, `None` if it is not made in a manufacturer or makes no item.
+fn formula(
+    recipe: &Value,
+    items: &HashMap<&str, Descriptor>,
+    machines: &HashMap<&str, (Rc<Machine>, Option<Fraction>)>,
+) -> Result<Option<Formula>> {
+    let machine = field(recipe, "mProducedIn")?
+        .split(',')
+        .find_map(|x| machines.get(class_name(x)));
+    let Some((machine, _)) = machine else {
+        return Ok(None);
+    };
+
+    let mut parts = Vec::new();
+    for key in ["mProduct", "mIngredients"] {
+        let mut v = Vec::new();
+        for (class, amount) in amounts(field(recipe, key)?) {
+            let Some(d) = items.get(class) else {
+                return Ok(None);
+            };
+            let amount = if d.fluid {
+                Fraction::new(amount, 1000)
+            } else {
+                Fraction::new(amount, 1)
+            };
+            v.push((d.item.clone(), amount));
+        }
+        parts.push(v);
+    }
+    let ingredients = parts.pop().unwrap();
+    let products = parts.pop().unwrap();
+    if products.is_empty() {
+        return Ok(None);
+    }
+
+    let items = |v: Vec<(Rc<Item>, Fraction)>| -> Vec<FormulaItem> {
+        v.into_iter()
+            .map(|(item, x)| FormulaItem::new(item, x))
+            .collect()
+    };
+    let duration = field(recipe, "mManufactoringDuration")?;
+    let seconds = decimal(duration)
+        .filter(|x| *x != 0.into())
+        .ok_or_else(|| Error::InvalidDocs(format!("invalid duration {duration}")))?;
+    let speed = Fraction::from(60).checked_div(seconds)?;
+    Ok(Some(Formula::new(
+        items(products),
+        items(ingredients),
+        speed,
+        Some(machine.clone()),
+    )))
+}
+
+/// Item classes and amounts of `((ItemClass=...Desc_OreIron.Desc_OreIron_C"',Amount=1),...)`.
+fn amounts(v: &str) -> Vec<(&str, u64)> {
+    v.split("ItemClass=")
+        .skip(1)
+        .filter_map(|x| {
+            let (class, rest) = x.split_once(",Amount=")?;
+            let end = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            Some((class_name(class), rest[..end].parse().ok()?))
+        })
+        .collect()
+}
+
+/// `Build_SmelterMk1_C` of a path like `"/Game/.../Build_SmelterMk1.Build_SmelterMk1_C"`.
+fn class_name(path: &str) -> &str {
+    let v = path.rsplit('.').next().unwrap_or_default();
+    v.trim_matches(|c: char| matches!(c, '"' | '\'' | '(' | ')') || c.is_whitespace())
+}
+
+/// `2.500000` as a fraction.
+fn decimal(v: &str) -> Option<Fraction> {
+    let (whole, part) = v.split_once('.').unwrap_or((v, ""));
+    let part = part.trim_end_matches('0');
+    let unit = 10u64.checked_pow(part.len() as u32)?;
+    let whole: u64 = whole.parse().ok()?;
+    let part: u64 = if part.is_empty() {
+        0
+    } else {
+        part.parse().ok()?
+    };
+    Some(Fraction::new(
+        whole.checked_mul(unit)?.checked_add(part)?,
+        unit,
+    ))
+}
+
+/// Text of the file, which the game writes in UTF-16 little endian.
+fn decode(bytes: &[u8]) -> Result<String> {
+    let utf16 = |bytes: &[u8], f: fn([u8; 2]) -> u16| {
+        let v: Vec<u16> = bytes.chunks_exact(2).map(|x| f([x[0], x[1]])).collect();
+        String::from_utf16(&v).map_err(|e| Error::InvalidDocs(e.to_string()))
+    };
+    match bytes {
+        [0xff, 0xfe, rest @ ..] => utf16(rest, u16::from_le_bytes),
+        [0xfe, 0xff, rest @ ..] => utf16(rest, u16::from_be_bytes),
+        [0xef, 0xbb, 0xbf, rest @ ..] | rest => {
+            String::from_utf8(rest.to_vec()).map_err(|e| Error::InvalidDocs(e.to_string()))
+        }
+    }
+}
+
+fn field<'a>(class: &'a Value, key: &str) -> Result<&'a str> {
+    class[key]
+        .as_str()
+        .ok_or_else(|| Error::InvalidDocs(format!("missing {key}")))
+}
+
+fn invalid(e: serde_json::Error) -> Error {
+    Error::InvalidDocs(e.to_string())
+}
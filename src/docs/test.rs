@@ -0,0 +1,82 @@
+use super::*;
+
+const DOCS: &str = r#"[
+{"NativeClass": "/Script/CoreUObject.Class'/Script/FactoryGame.FGResourceDescriptor'", "Classes": [
+  {"ClassName": "Desc_OreIron_C", "mDisplayName": "Iron Ore", "mForm": "RF_SOLID"},
+  {"ClassName": "Desc_Water_C", "mDisplayName": "Water", "mForm": "RF_LIQUID"}
+]},
+{"NativeClass": "/Script/CoreUObject.Class'/Script/FactoryGame.FGItemDescriptor'", "Classes": [
+  {"ClassName": "Desc_IronIngot_C", "mDisplayName": "Iron Ingot", "mForm": "RF_SOLID"},
+  {"ClassName": "Desc_IronPlate_C", "mDisplayName": "Iron Plate", "mForm": "RF_SOLID"}
+]},
+{"NativeClass": "/Script/CoreUObject.Class'/Script/FactoryGame.FGBuildableManufacturer'", "Classes": [
+  {"ClassName": "Build_SmelterMk1_C", "mDisplayName": "Smelter", "mPowerConsumption": "4.000000"},
+  {"ClassName": "Build_FoundryMk1_C", "mDisplayName": "Refinery", "mPowerConsumption": "30.000000"},
+  {"ClassName": "Build_ConstructorMk1_C", "mDisplayName": "Constructor", "mPowerConsumption": "4.000000"}
+]},
+{"NativeClass": "/Script/CoreUObject.Class'/Script/FactoryGame.FGRecipe'", "Classes": [
+  {"ClassName": "Recipe_Alternate_PureIronIngot_C",
+   "mIngredients": "((ItemClass=/Script/Engine.BlueprintGeneratedClass'\"/Game/FactoryGame/Resource/RawResources/OreIron/Desc_OreIron.Desc_OreIron_C\"',Amount=7),(ItemClass=/Script/Engine.BlueprintGeneratedClass'\"/Game/FactoryGame/Resource/RawResources/Water/Desc_Water.Desc_Water_C\"',Amount=4500))",
+   "mProduct": "((ItemClass=/Script/Engine.BlueprintGeneratedClass'\"/Game/FactoryGame/Resource/Parts/IronIngot/Desc_IronIngot.Desc_IronIngot_C\"',Amount=13))",
+   "mManufactoringDuration": "12.000000",
+   "mProducedIn": "(\"/Game/FactoryGame/Buildable/Factory/FoundryMk1/Build_FoundryMk1.Build_FoundryMk1_C\")"},
+  {"ClassName": "Recipe_IngotIron_C",
+   "mIngredients": "((ItemClass=/Script/Engine.BlueprintGeneratedClass'\"/Game/FactoryGame/Resource/RawResources/OreIron/Desc_OreIron.Desc_OreIron_C\"',Amount=1))",
+   "mProduct": "((ItemClass=/Script/Engine.BlueprintGeneratedClass'\"/Game/FactoryGame/Resource/Parts/IronIngot/Desc_IronIngot.Desc_IronIngot_C\"',Amount=1))",
+   "mManufactoringDuration": "2.000000",
+   "mProducedIn": "(\"/Game/FactoryGame/Buildable/Factory/SmelterMk1/Build_SmelterMk1.Build_SmelterMk1_C\",\"/Game/FactoryGame/Buildable/-Shared/WorkBench/BP_WorkBenchComponent.BP_WorkBenchComponent_C\")"},
+  {"ClassName": "Recipe_IronPlate_C",
+   "mIngredients": "((ItemClass=/Script/Engine.BlueprintGeneratedClass'\"/Game/FactoryGame/Resource/Parts/IronIngot/Desc_IronIngot.Desc_IronIngot_C\"',Amount=3))",
+   "mProduct": "((ItemClass=/Script/Engine.BlueprintGeneratedClass'\"/Game/FactoryGame/Resource/Parts/IronPlate/Desc_IronPlate.Desc_IronPlate_C\"',Amount=2))",
+   "mManufactoringDuration": "6.000000",
+   "mProducedIn": "(\"/Game/FactoryGame/Buildable/Factory/ConstructorMk1/Build_ConstructorMk1.Build_ConstructorMk1_C\")"},
+  {"ClassName": "Recipe_HandOnly_C",
+   "mIngredients": "((ItemClass=/Script/Engine.BlueprintGeneratedClass'\"/Game/FactoryGame/Resource/Parts/IronPlate/Desc_IronPlate.Desc_IronPlate_C\"',Amount=1))",
+   "mProduct": "((ItemClass=/Script/Engine.BlueprintGeneratedClass'\"/Game/FactoryGame/Resource/Parts/IronIngot/Desc_IronIngot.Desc_IronIngot_C\"',Amount=1))",
+   "mManufactoringDuration": "1.000000",
+   "mProducedIn": "(\"/Game/FactoryGame/Buildable/-Shared/WorkBench/BP_WorkBenchComponent.BP_WorkBenchComponent_C\")"}
+]}
+]"#;
+
+fn utf16(v: &str) -> Vec<u8> {
+    let mut bytes = vec![0xff, 0xfe];
+    bytes.extend(v.encode_utf16().flat_map(|x| x.to_le_bytes()));
+    bytes
+}
+
+#[test]
+fn test_from_docs() {
+    let list = FormulaList::from_docs(&utf16(DOCS)[..]).unwrap();
+    let formulas: Vec<_> = list.formulas().iter().map(|x| x.to_string()).collect();
+    assert_eq!(
+        formulas,
+        [
+            "Iron Ore*1 = 60/min",
+            "Water*1 = 60/min",
+            "Iron Ingot*1 = Iron Ore*1 30/min @Smelter",
+            "Iron Plate*2 = Iron Ingot*3 10/min @Constructor",
+            "Iron Ingot*13 = Iron Ore*7 + Water*9/2 5/min @Refinery",
+        ]
+    );
+    let smelter = list.formulas()[2].machine().unwrap();
+    assert_eq!(list.power(&smelter), Some(4.into()));
+    assert_eq!(list.machines().len(), 3);
+    assert_eq!(FormulaList::from_docs(DOCS.as_bytes()).unwrap(), list);
+
+    assert!(matches!(
+        FormulaList::from_docs(&utf16("{}")[..]),
+        Err(Error::InvalidDocs(_))
+    ));
+    assert!(matches!(
+        FormulaList::from_docs(&[0xff, 0xfe, 0x00, 0xd8][..]),
+        Err(Error::InvalidDocs(_))
+    ));
+}
+
+#[test]
+fn test_decimal() {
+    assert_eq!(decimal("2.000000"), Some(2.into()));
+    assert_eq!(decimal("0.500000"), Some(Fraction::new(1, 2)));
+    assert_eq!(decimal("12"), Some(12.into()));
+    assert_eq!(decimal("x.5"), None);
+}
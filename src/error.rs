@@ -1,23 +1,115 @@
-use std::fmt::{Debug, Display};
+use std::{
+    fmt::{Debug, Display},
+    num::ParseIntError,
+    ops::Range,
+    path::PathBuf,
+    rc::Rc,
+};
+
+use crate::{Formula, Item, Machine, Transport};
 
 pub enum Error {
+    /// the char at line, column, both from 1, is not the expected token
     UnknownChar(String, char, usize, usize),
+    /// the input ended at line, column instead of the expected token
+    UnexpectedEof(String, usize, usize),
+    /// a number at line, column is not a valid amount
+    InvalidAmount(String, usize, usize, Option<ParseIntError>),
+    /// the same formula is declared again at line
+    DuplicateRecipe(Rc<Formula>, usize),
+    /// the power of the machine is declared again at line
+    DuplicateMachine(Rc<Machine>, usize),
+    /// a transport with the same name is declared again at line
+    DuplicateTransport(Rc<Transport>, usize),
+    /// the supply of the item is declared again at line
+    DuplicateSupply(Rc<Item>, usize),
+    /// the item consumed at line is neither produced nor supplied by a line
+    UnproducedItem(Rc<Item>, usize),
+    /// no item with this name in the formula list
+    UnknownItem(String),
+    /// no formula produces the item
+    MissingRecipe(Rc<Item>),
+    /// the item has no recipe with this index
+    UnknownRecipe(Rc<Item>, usize),
+    Io(std::io::Error),
+    /// a number does not fit in its representation
+    Overflow,
+    /// no production plan satisfies all constraints
+    Infeasible,
+    /// the objective can be improved without limit
+    Unbounded,
+    /// formulas of these items feed each other but can not reach a steady state
+    Cycle(Vec<Rc<Item>>),
+    /// the plan needs more power than the limit in MW
+    PowerLimit(u32),
+    /// the plan needs more of these items than their supply
+    SupplyLimit(Vec<Rc<Item>>),
+    /// the `Docs.json` of the game can not be imported, for the reason
+    InvalidDocs(String),
+    /// the included file can not be read or parsed
+    Include(PathBuf, Box<Error>),
+    /// the file includes itself, directly or through other files
+    IncludeCycle(PathBuf),
+    /// the recipe lists being merged declare these differently
+    MergeConflict(Vec<String>),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
 impl std::error::Error for Error {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        None
+        match self {
+            Error::Io(e) => Some(e),
+            Error::InvalidAmount(_, _, _, Some(e)) => Some(e),
+            Error::Include(_, e) => Some(e.as_ref()),
+            _ => None,
+        }
     }
 }
 
 impl Debug for Error {
     fn fmt(&self, _f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Error::UnknownChar(target,c, line, column) => {
-                write!(_f, "unknown char{{target={target} char='{c}', line={line}, column={column}}}")
+            Error::UnknownChar(expected, c, line, column) => {
+                write!(_f, "unknown char{{expected={expected} char='{c}', line={line}, column={column}}}")
+            }
+            Error::UnexpectedEof(expected, line, column) => {
+                write!(_f, "unexpected eof{{expected={expected} line={line}, column={column}}}")
+            }
+            Error::InvalidAmount(value, line, column, e) => {
+                write!(_f, "invalid amount{{value={value} line={line}, column={column}, source={e:?}}}")
+            }
+            Error::DuplicateRecipe(formula, line) => {
+                write!(_f, "duplicate recipe{{formula={formula:?} line={line}}}")
+            }
+            Error::DuplicateMachine(machine, line) => {
+                write!(_f, "duplicate machine{{machine={machine:?} line={line}}}")
+            }
+            Error::DuplicateTransport(transport, line) => {
+                write!(_f, "duplicate transport{{transport={transport:?} line={line}}}")
             }
+            Error::DuplicateSupply(item, line) => {
+                write!(_f, "duplicate supply{{item={item:?} line={line}}}")
+            }
+            Error::UnproducedItem(item, line) => {
+                write!(_f, "unproduced item{{item={item:?} line={line}}}")
+            }
+            Error::UnknownItem(name) => write!(_f, "unknown item{{name={name}}}"),
+            Error::MissingRecipe(item) => write!(_f, "missing recipe{{item={item:?}}}"),
+            Error::UnknownRecipe(item, index) => {
+                write!(_f, "unknown recipe{{item={item:?} index={index}}}")
+            }
+            Error::Io(e) => write!(_f, "io{{{e:?}}}"),
+            Error::Overflow => write!(_f, "overflow"),
+            Error::Infeasible => write!(_f, "infeasible"),
+            Error::Unbounded => write!(_f, "unbounded"),
+            Error::Cycle(items) => write!(_f, "cycle{items:?}"),
+            Error::PowerLimit(limit) => write!(_f, "power limit{{limit={limit}}}"),
+            Error::SupplyLimit(items) => write!(_f, "supply limit{items:?}"),
+            Error::InvalidDocs(reason) => write!(_f, "invalid docs{{reason={reason}}}"),
+            Error::Include(path, e) => write!(_f, "include{{path={path:?} source={e:?}}}"),
+            Error::IncludeCycle(path) => write!(_f, "include cycle{{path={path:?}}}"),
+            Error::MergeConflict(v) => write!(_f, "merge conflict{v:?}"),
         }
     }
 }
@@ -25,21 +117,134 @@ impl Debug for Error {
 impl Display for Error {
     fn fmt(&self, _f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Error::UnknownChar(target,c, line, column) => {
-                write!(_f, "Unknown char '{}' parsing {target} at line {}, col {}", c, line, column)
+            Error::UnknownChar(expected, c, line, column) => {
+                write!(_f, "Unknown char '{}' at line {}, col {}, expected {expected}", c.escape_debug(), line, column)
+            }
+            Error::UnexpectedEof(expected, line, column) => {
+                write!(_f, "Unexpected end of input at line {line}, col {column}, expected {expected}")
+            }
+            Error::InvalidAmount(value, line, column, _) => {
+                write!(_f, "Invalid amount '{value}' at line {line}, col {column}")
+            }
+            Error::DuplicateRecipe(formula, line) => {
+                write!(_f, "Duplicate recipe {formula} at line {line}")
+            }
+            Error::DuplicateMachine(machine, line) => {
+                write!(_f, "Duplicate power of machine {machine} at line {line}")
+            }
+            Error::DuplicateTransport(transport, line) => {
+                write!(_f, "Duplicate transport {transport} at line {line}")
             }
+            Error::DuplicateSupply(item, line) => {
+                write!(_f, "Duplicate supply of {item} at line {line}")
+            }
+            Error::UnproducedItem(item, line) => {
+                write!(_f, "Item {item} at line {line} is neither produced nor supplied")
+            }
+            Error::UnknownItem(name) => write!(_f, "Unknown item {name}"),
+            Error::MissingRecipe(item) => write!(_f, "No recipe produces {item}"),
+            Error::UnknownRecipe(item, index) => write!(_f, "Item {item} has no recipe {index}"),
+            Error::Io(e) => write!(_f, "Failed to read formulas: {e}"),
+            Error::Overflow => write!(_f, "Number overflow in calculation"),
+            Error::Infeasible => write!(_f, "No production plan satisfies all constraints"),
+            Error::Unbounded => write!(_f, "The objective is unbounded"),
+            Error::Cycle(items) => write!(_f, "Unsolvable loop among items {items:?}"),
+            Error::PowerLimit(limit) => write!(_f, "The plan needs more than {limit}MW of power"),
+            Error::SupplyLimit(items) => write!(_f, "Not enough supply of {items:?}"),
+            Error::InvalidDocs(reason) => write!(_f, "Invalid Docs.json: {reason}"),
+            Error::Include(path, e) => write!(_f, "{}: {e}", path.display()),
+            Error::IncludeCycle(path) => write!(_f, "{} includes itself", path.display()),
+            Error::MergeConflict(v) => write!(_f, "Conflicting {} when merging", v.join(", ")),
         }
     }
 }
 
 impl From<std::io::Error> for Error {
-    fn from(_: std::io::Error) -> Self {
-        todo!()
+    fn from(value: std::io::Error) -> Self {
+        Error::Io(value)
     }
 }
 
-impl From<std::num::ParseIntError> for Error {
-    fn from(_value: std::num::ParseIntError) -> Self {
-        todo!()
+/// An error in a line of a recipe file, the parser goes on with the next line,
+/// or a warning about a line it accepted.
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub error: Error,
+    /// line of the error, from 1
+    pub line: usize,
+    /// chars of the line the error is about, from 0
+    pub span: Range<usize>,
+    /// the token the parser expected there, if it stopped at an unexpected char
+    pub expected: Option<String>,
+    /// the line without its line break
+    pub source: String,
+}
+
+impl Diagnostic {
+    /// The diagnostic of `error` in `source`, the text of line `line`.
+    pub(crate) fn new(error: Error, line: usize, source: String) -> Self {
+        let len = source.chars().count();
+        let indent = source.chars().take_while(|c| c.is_whitespace()).count();
+        let (span, expected) = match &error {
+            // the columns count from 1, the span from 0
+            Error::UnknownChar(expected, _, _, column) => (*column - 1..*column, Some(expected.clone())),
+            Error::UnexpectedEof(expected, _, column) => {
+                (*column - 1..*column - 1, Some(expected.clone()))
+            }
+            Error::InvalidAmount(v, _, column, _) => {
+                (*column - 1..*column - 1 + v.chars().count(), None)
+            }
+            Error::UnproducedItem(item, _) => match input(&source, &item.to_string()) {
+                Some(start) => (start..start + item.to_string().chars().count(), None),
+                None => (indent..len, None),
+            },
+            _ => (indent..len, None),
+        };
+        Diagnostic {
+            error,
+            line,
+            span,
+            expected,
+            source,
+        }
+    }
+
+    /// The line is accepted but may not mean what it says, e.g. it consumes an item nothing produces.
+    pub fn is_warning(&self) -> bool {
+        matches!(self.error, Error::UnproducedItem(..))
+    }
+}
+
+/// Char index of the input `name` in `source`, the first one after `=`.
+fn input(source: &str, name: &str) -> Option<usize> {
+    let chars: Vec<char> = source.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let start = chars.iter().position(|c| *c == '=')?;
+    (start..chars.len()).find(|i| {
+        chars[*i..].starts_with(&name)
+            && !chars[i - 1].is_alphanumeric()
+            && chars.get(i + name.len()).is_none_or(|c| !c.is_alphanumeric())
+    })
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let located = matches!(
+            self.error,
+            Error::UnknownChar(..)
+                | Error::UnexpectedEof(..)
+                | Error::InvalidAmount(..)
+                | Error::DuplicateRecipe(..)
+                | Error::DuplicateMachine(..)
+                | Error::DuplicateTransport(..)
+                | Error::DuplicateSupply(..)
+                | Error::UnproducedItem(..)
+                | Error::Include(..)
+        );
+        if located {
+            write!(f, "{}", self.error)
+        } else {
+            write!(f, "{} at line {}", self.error, self.line)
+        }
     }
 }
@@ -5,19 +5,45 @@
 //! 加强铁板 = 6*铁板 + 12*螺丝 12s
 //! ```
 //! It means that 6 iron plates and 12 screws can be made into 1 reinforced iron plate in 12 seconds. 
+//! A formula may have byproducts listed after its main output, e.g. `2*塑料 + 1*重油残渣 = 3*原油 6s`,
+//! and may name the [`Machine`] running it, e.g. `加强铁板 = 6*铁板 + 12*螺丝 12s @装配器`.
+//! Amounts and times may be decimals or fractions, e.g. `燃料 = 3/2*水 + 1.5*原油 0.5s`,
+//! times may be `500ms` or `2m` as well, and the speed may be given as the rate of the main
+//! output instead, e.g. `2*铁板 = 3*铁锭 @ 20/min`.
+//! The power a machine draws is declared on its own line, e.g. `@装配器 15MW`.
+//! So is a [`Transport`] with the items per minute of its tiers and the items it carries,
+//! e.g. `~管道 300 600: 水 + 原油`, one without items carries all the others,
+//! and the most of a raw item that can be produced, e.g. `铁矿石 <= 480/min`.
+//! Another recipe file is read in place of `include "alternate.txt"`, relative to the file
+//! including it and only the first time it is included, see [`FormulaList::from_path`].
+//! Without a quoted path after it, `include` is an item name like any other.
 //! The [`FormulaList`] is a group of formulas separated by new line. 
+//! Blank lines are skipped, spaces and tabs may surround every part of a line,
+//! and `#` starts a comment running to the end of the line.
 //! Note that the [`Formula`] and [`Item`] are wrapped in [`Rc`] to avoid duplication.
+//! With the `serde` feature a list and its plans can be exchanged as JSON as well, where
+//! items and machines are their names and fractions are `"n/d"` strings. A list the parser
+//! would reject, e.g. with a duplicate recipe or a zero amount, fails to deserialize.
 //!
 //! The [ABNF](https://www.rfc-editor.org/rfc/rfc5234.txt) of the formula shows here:
 //! ```abnf
-//! formula_list   = formula *( new_line formula ) [new_line]
-//! formula        = formula_target [*SP "=" *SP formula_source ] 1*SP speed
-//! formula_target = formula_item
-//! formula_source = formula_item *( *SP "+" *SP formula_item)
+//! formula_list   = line *( new_line line )
+//! line           = *WSP [ ( formula / machine_power / transport / supply / include ) *WSP ] [comment]
+//! comment        = "#" *( WSP / %x21-10FFFF )    ; to the end of the line
+//! formula        = formula_target [*WSP "=" *WSP formula_source ] 1*WSP speed [1*WSP machine]
+//! formula_target = formula_item *( *WSP "+" *WSP formula_item)    ; main output first, then byproducts
+//! formula_source = formula_item *( *WSP "+" *WSP formula_item)
 //! formula_item   = [amount "*"] item
-//! amount         = 1*DIGIT
+//! amount         = number
+//! number         = 1*DIGIT ["." 1*DIGIT] ["/" 1*DIGIT]    ; e.g. 2, 1.5 or 3/2, not zero
 //! item           = ALPHA 1*( ALPHA / DIGIT )    ; consider ALPHA as Alphabetic in unicode
-//! speed          = 1*DIGIT "s"    ; "s" is short for seconds
+//! speed          = number ( "ms" / "s" / "m" ) / rate    ; a duration in milliseconds, seconds or minutes
+//! rate           = "@" *WSP number "/" ( "s" / "min" / "h" )    ; items of the main output per second, minute or hour
+//! machine        = "@" item       ; the building running the formula
+//! machine_power  = machine 1*WSP 1*DIGIT "MW"
+//! supply         = item *WSP "<=" *WSP 1*DIGIT "/min"
+//! transport      = "~" item 1*(1*WSP 1*DIGIT) [*WSP ":" *WSP item *( *WSP "+" *WSP item)]    ; tiers ascending
+//! include        = "include" 1*WSP DQUOTE 1*( %x20-21 / %x23-10FFFF ) DQUOTE
 //! new_line       = LF / CRLF
 //! ```
 
@@ -25,80 +51,575 @@
 mod test;
 
 use std::{
+    collections::HashMap,
     fmt::{Debug, Display, Formatter},
+    fs::File,
     io::{BufRead, BufReader, Read},
+    mem,
+    path::{Path, PathBuf},
     rc::Rc,
 };
 
-use crate::{Error, Fraction, Result};
+use crate::{simplex::Field, Diagnostic, Error, Fraction, Number, Result};
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "FormulaListData")
+)]
 pub struct FormulaList {
     items: Vec<Rc<Item>>,
+    machines: Vec<Rc<Machine>>,
     formulas: Vec<Rc<Formula>>,
+    power: HashMap<Rc<Machine>, Fraction>,
+    transports: Vec<Rc<Transport>>,
+    supply: HashMap<Rc<Item>, Fraction>,
 }
 
 impl FormulaList {
-    pub fn formulas<'a>(&'a self) -> &[Rc<Formula>] {
+    /// Empty list, filled by a parser or importer.
+    pub(crate) fn new() -> Self {
+        FormulaList {
+            items: Vec::new(),
+            machines: Vec::new(),
+            formulas: Vec::new(),
+            power: HashMap::new(),
+            transports: Vec::new(),
+            supply: HashMap::new(),
+        }
+    }
+
+    /// `item` is used by a formula, a transport or a supply of the list.
+    fn declares(&self, item: &Rc<Item>) -> bool {
+        self.formulas
+            .iter()
+            .any(|x| x.target.iter().chain(&x.source).any(|x| &x.0 == item))
+            || self.transports.iter().any(|x| x.items.contains(item))
+            || self.supply.contains_key(item)
+    }
+
+    /// Add `formula` declared at `line`, sharing the equal items and machines of the list.
+    pub(crate) fn push(&mut self, formula: Formula, line: usize) -> Result<()> {
+        let mut items = |x: Vec<FormulaItem>| -> Vec<FormulaItem> {
+            x.into_iter()
+                .map(|FormulaItem(item, amount)| FormulaItem(shared(&mut self.items, item), amount))
+                .collect()
+        };
+        let formula = Formula {
+            target: items(formula.target),
+            source: items(formula.source),
+            speed: formula.speed,
+            machine: formula.machine.map(|x| shared(&mut self.machines, x)),
+        };
+        if self.formulas.iter().any(|x| **x == formula) {
+            return Err(Error::DuplicateRecipe(Rc::new(formula), line));
+        }
+        self.formulas.push(Rc::new(formula));
+        Ok(())
+    }
+
+    /// Power of every building of `machine` in MW.
+    pub(crate) fn set_power(&mut self, machine: Rc<Machine>, v: Fraction) {
+        let machine = shared(&mut self.machines, machine);
+        self.power.insert(machine, v);
+    }
+
+    pub fn formulas(&self) -> &[Rc<Formula>] {
         &self.formulas
     }
 
+    pub fn items(&self) -> &[Rc<Item>] {
+        &self.items
+    }
+
+    pub fn machines(&self) -> &[Rc<Machine>] {
+        &self.machines
+    }
+
+    /// Power in MW one building of `machine` draws, if declared.
+    pub fn power(&self, machine: &Machine) -> Option<Fraction> {
+        self.power.get(machine).copied()
+    }
+
+    /// Most of `item` per minute all plans can produce, if limited.
+    pub fn supply(&self, item: &Item) -> Option<Fraction> {
+        self.supply.get(item).copied()
+    }
+
+    /// Limit the supply of `item`, replacing the declared one.
+    pub fn set_supply(&mut self, item: Rc<Item>, v: Fraction) {
+        self.supply.insert(item, v);
+    }
+
+    /// Items with a limited supply, in the order of [`FormulaList::items`].
+    pub fn limited(&self) -> Vec<Rc<Item>> {
+        self.items
+            .iter()
+            .filter(|x| self.supply.contains_key(*x))
+            .cloned()
+            .collect()
+    }
+
+    pub fn transports(&self) -> &[Rc<Transport>] {
+        &self.transports
+    }
+
+    /// The transport carrying `item`, the first one without items if none lists it.
+    pub fn transport(&self, item: &Item) -> Option<Rc<Transport>> {
+        let listed = self
+            .transports
+            .iter()
+            .find(|x| x.items.iter().any(|x| **x == *item));
+        listed
+            .or_else(|| self.transports.iter().find(|x| x.items.is_empty()))
+            .cloned()
+    }
+
+    /// All formulas producing `item`, those with it as main output first and then those with it
+    /// as byproduct, each in the order they are declared. The index in the returned list is
+    /// the recipe index used by [`crate::RecipeSelection`], so a byproduct formula never
+    /// shifts the index of a dedicated recipe.
+    pub fn recipes(&self, item: &Item) -> Vec<Rc<Formula>> {
+        let main = self.formulas.iter().filter(|x| *x.target().item() == *item);
+        let byproduct = self
+            .formulas
+            .iter()
+            .filter(|x| *x.target().item() != *item && x.output(item).is_some());
+        main.chain(byproduct).cloned().collect()
+    }
+
+    /// Parse a recipe file, the files it includes are relative to the current directory.
     pub fn from_reader(source: impl Read) -> Result<Self> {
         let mut reader = ParserReader::new(source);
         <FormulaList as Parsed>::parse(&mut reader)
     }
 
-    pub fn get_item(&self, name: &str) -> Rc<Item> {
+    /// Parse the recipe file at `path`, the files it includes are relative to it.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        strict(FormulaList::parse_path(path)?)
+    }
+
+    /// Like [`FormulaList::from_reader`], but a line with an error is skipped, returning
+    /// what was parsed and the [`Diagnostic`] of each such line, along with a warning for each
+    /// item consumed but neither produced nor supplied. Only a failed read is an error.
+    pub fn parse(source: impl Read) -> Result<(Self, Vec<Diagnostic>)> {
+        let mut reader = ParserReader::new(source);
+        parse_all(&mut reader)
+    }
+
+    /// Like [`FormulaList::from_path`], but collecting the diagnostics like [`FormulaList::parse`].
+    /// An included file with errors is a single diagnostic at its `include` line.
+    pub fn parse_path(path: impl AsRef<Path>) -> Result<(Self, Vec<Diagnostic>)> {
+        let path = path.as_ref().canonicalize()?;
+        let mut reader = ParserReader::new(File::open(&path)?);
+        reader.ctx.dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        reader.ctx.including.push(path);
+        parse_all(&mut reader)
+    }
+
+    /// Add everything declared in `other`, sharing the items and machines with equal names.
+    /// Nothing is added if a recipe is in both lists, or the power of a machine, a transport
+    /// or a supply is declared differently in each.
+    pub fn merge(&mut self, other: &FormulaList) -> Result<()> {
+        let mut conflicts = Vec::new();
+        for formula in other.formulas.iter() {
+            if self.formulas.contains(formula) {
+                conflicts.push(format!("recipe {formula}"));
+            }
+        }
+        for (machine, v) in other.power.iter() {
+            if self.power.get(machine).is_some_and(|x| x != v) {
+                conflicts.push(format!("power of {machine}"));
+            }
+        }
+        for transport in other.transports.iter() {
+            if self
+                .transports
+                .iter()
+                .any(|x| x.name == transport.name && x != transport)
+            {
+                conflicts.push(format!("transport {transport}"));
+            }
+        }
+        for (item, v) in other.supply.iter() {
+            if self.supply.get(item).is_some_and(|x| x != v) {
+                conflicts.push(format!("supply of {item}"));
+            }
+        }
+        if !conflicts.is_empty() {
+            return Err(Error::MergeConflict(conflicts));
+        }
+
+        for item in other.items.iter() {
+            shared(&mut self.items, item.clone());
+        }
+        for machine in other.machines.iter() {
+            shared(&mut self.machines, machine.clone());
+        }
+        for formula in other.formulas.iter() {
+            self.push((**formula).clone(), 0)?;
+        }
+        for (machine, v) in other.power.iter() {
+            self.set_power(machine.clone(), *v);
+        }
+        for transport in other.transports.iter() {
+            if self.transports.iter().all(|x| x.name != transport.name) {
+                let items = transport
+                    .items
+                    .iter()
+                    .map(|x| shared(&mut self.items, x.clone()))
+                    .collect();
+                self.transports.push(Rc::new(Transport {
+                    name: transport.name.clone(),
+                    tiers: transport.tiers.clone(),
+                    items,
+                }));
+            }
+        }
+        for (item, v) in other.supply.iter() {
+            let item = shared(&mut self.items, item.clone());
+            self.supply.insert(item, *v);
+        }
+        Ok(())
+    }
+
+    pub fn get_item(&self, name: &str) -> Result<Rc<Item>> {
         for v in self.items.iter() {
             if v.name == name {
-                return v.clone();
+                return Ok(v.clone());
             }
         }
-        todo!("not found")
+        Err(Error::UnknownItem(name.to_string()))
+    }
+}
+
+/// [`FormulaList`] as deserialized, where equal items and machines are not shared yet.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct FormulaListData {
+    items: Vec<Rc<Item>>,
+    machines: Vec<Rc<Machine>>,
+    formulas: Vec<Formula>,
+    power: HashMap<Rc<Machine>, Fraction>,
+    transports: Vec<Transport>,
+    supply: HashMap<Rc<Item>, Fraction>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<FormulaListData> for FormulaList {
+    type Error = String;
+
+    /// Share the equal items and machines, rejecting what the parser would.
+    fn try_from(v: FormulaListData) -> std::result::Result<Self, String> {
+        let mut list = FormulaList::new();
+        for item in v.items {
+            shared(&mut list.items, item);
+        }
+        for machine in v.machines {
+            shared(&mut list.machines, machine);
+        }
+        for formula in v.formulas {
+            list.push(formula, 0).map_err(|e| match e {
+                Error::DuplicateRecipe(formula, _) => format!("duplicate recipe {formula}"),
+                e => e.to_string(),
+            })?;
+        }
+        for (machine, power) in v.power {
+            if power.amount() == 0 {
+                return Err(format!("zero power of machine {machine}"));
+            }
+            list.set_power(machine, power);
+        }
+        for transport in v.transports {
+            if list.transports.iter().any(|x| x.name == transport.name) {
+                return Err(format!("duplicate transport {transport}"));
+            }
+            let items = transport
+                .items
+                .into_iter()
+                .map(|x| shared(&mut list.items, x))
+                .collect();
+            list.transports.push(Rc::new(Transport { items, ..transport }));
+        }
+        for (item, supply) in v.supply {
+            if supply.amount() == 0 {
+                return Err(format!("zero supply of {item}"));
+            }
+            let item = shared(&mut list.items, item);
+            list.supply.insert(item, supply);
+        }
+        Ok(list)
+    }
+}
+
+/// The value in `list` equal to `v`, which is added if there is none.
+fn shared<T: PartialEq>(list: &mut Vec<Rc<T>>, v: Rc<T>) -> Rc<T> {
+    match list.iter().find(|x| **x == v) {
+        Some(x) => x.clone(),
+        None => {
+            list.push(v.clone());
+            v
+        }
     }
 }
 
 impl Parsed for FormulaList {
     fn first(c: char) -> bool {
         <Formula as Parsed>::first(c)
+            || <MachinePower as Parsed>::first(c)
+            || <Transport as Parsed>::first(c)
     }
 
     fn parse(reader: &mut impl ParserRead) -> Result<FormulaList> {
-        let mut formulas: Vec<Rc<Formula>> = Vec::new();
-
-        let formula = <Formula as Parsed>::parse(reader)?;
-        formulas.push(Rc::new(formula));
+        strict(parse_all(reader)?)
+    }
+}
 
-        while <NewLine as Parsed>::first(reader.peek()?) {
-            _ = <NewLine as Parsed>::parse(reader)?;
+/// The lines parsed without error and the diagnostics of the others.
+fn parse_all(reader: &mut impl ParserRead) -> Result<(FormulaList, Vec<Diagnostic>)> {
+    let mut list = FormulaList::new();
+    let mut diagnostics = Vec::new();
+    let mut declared = Vec::new();
+    parse_lines(reader, &mut list, &mut diagnostics, &mut declared)?;
+    // names are interned while parsing, keep only those of the lines accepted
+    let ctx = reader.ctx();
+    list.items = ctx
+        .items
+        .iter()
+        .filter(|x| list.declares(x))
+        .cloned()
+        .collect();
+    list.machines = ctx
+        .machines
+        .iter()
+        .filter(|x| {
+            list.power.contains_key(*x)
+                || list.formulas.iter().any(|f| f.machine.as_ref() == Some(x))
+        })
+        .cloned()
+        .collect();
+    // an item consumed but never produced may be a typo of another one
+    for item in list.items.iter() {
+        if list.supply.contains_key(item) || list.formulas.iter().any(|x| x.output(item).is_some()) {
+            continue;
+        }
+        let consumer = list
+            .formulas
+            .iter()
+            .position(|x| x.source.iter().any(|x| x.0 == *item));
+        if let Some(i) = consumer {
+            let (line, source) = &declared[i];
+            let e = Error::UnproducedItem(item.clone(), *line);
+            diagnostics.push(Diagnostic::new(e, *line, source.clone()));
+        }
+    }
+    diagnostics.sort_by_key(|x| x.line);
+    Ok((list, diagnostics))
+}
 
-            if reader.peek()? == '\0' {
-                break;
+/// Parse every line of a recipe file into `list`, skipping blank lines and comments.
+/// A line with an error is added to `diagnostics` and skipped, only reading fails.
+/// The line number and text of the line adding each formula are added to `declared`.
+fn parse_lines(
+    reader: &mut impl ParserRead,
+    list: &mut FormulaList,
+    diagnostics: &mut Vec<Diagnostic>,
+    declared: &mut Vec<(usize, String)>,
+) -> Result<()> {
+    loop {
+        let formulas = list.formulas.len();
+        let r = parse_line_end(reader, list);
+        // an include line adds the formulas of the included file, even if one of its lines fails
+        let (line, _) = reader.location();
+        for _ in formulas..list.formulas.len() {
+            declared.push((line, reader.source_line()));
+        }
+        match r {
+            Ok(()) => {}
+            Err(e @ Error::Io(_)) => return Err(e),
+            Err(e) => {
+                let (line, _) = reader.location();
+                diagnostics.push(Diagnostic::new(e, line, reader.source_line()));
+                // resynchronise at the next line
+                while !matches!(reader.peek()?, '\n' | '\0') {
+                    _ = reader.read()?;
+                }
             }
+        }
 
-            let formula = <Formula as Parsed>::parse(reader)?;
-            formulas.push(Rc::new(formula));
+        if reader.peek()? == '\0' {
+            return Ok(());
         }
+        _ = <NewLine as Parsed>::parse(reader)?;
+    }
+}
 
-        Ok(FormulaList {
-            items: reader.ctx().items.clone(), // todo
-            formulas,
-        })
+/// Parse a line up to its line break, which may be blank or a comment.
+fn parse_line_end(reader: &mut impl ParserRead, list: &mut FormulaList) -> Result<()> {
+    reader.skip_space()?;
+    let c = reader.peek()?;
+    if !matches!(c, '\0') && !<Comment as Parsed>::first(c) && !<NewLine as Parsed>::first(c) {
+        parse_line(reader, list)?;
+    }
+    parse_end(reader)
+}
+
+/// Skip the spaces and the comment before the line break, which must come next.
+fn parse_end(reader: &mut impl ParserRead) -> Result<()> {
+    reader.skip_space()?;
+    if <Comment as Parsed>::first(reader.peek()?) {
+        _ = <Comment as Parsed>::parse(reader)?;
+    }
+
+    let c = reader.peek()?;
+    if c != '\0' && !<NewLine as Parsed>::first(c) {
+        err_unknown_char(reader, "the end of the line")?;
+    }
+    Ok(())
+}
+
+/// `list` if there is no diagnostic but warnings, otherwise the error of the first one.
+fn strict((list, diagnostics): (FormulaList, Vec<Diagnostic>)) -> Result<FormulaList> {
+    match diagnostics.into_iter().find(|x| !x.is_warning()) {
+        Some(v) => Err(v.error),
+        None => Ok(list),
+    }
+}
+
+/// Parse a formula, the power of a machine, a transport, the supply of an item
+/// or the included file into `list`, which is left as it is if the line has an error.
+fn parse_line(reader: &mut impl ParserRead, list: &mut FormulaList) -> Result<()> {
+    let c = reader.peek()?;
+    let (line, _) = reader.location();
+    if starts_include(reader) {
+        let Include(path) = <Include as Parsed>::parse(reader)?;
+        parse_end(reader)?;
+        return include(reader, list, path);
+    }
+    if <Transport as Parsed>::first(c) {
+        let transport = <Transport as Parsed>::parse(reader)?;
+        parse_end(reader)?;
+        if list.transports.iter().any(|x| x.name == transport.name) {
+            return Err(Error::DuplicateTransport(Rc::new(transport), line));
+        }
+        list.transports.push(Rc::new(transport));
+        return Ok(());
     }
+    if <MachinePower as Parsed>::first(c) {
+        let MachinePower(machine, v) = <MachinePower as Parsed>::parse(reader)?;
+        parse_end(reader)?;
+        if list.power.contains_key(&machine) {
+            return Err(Error::DuplicateMachine(machine, line));
+        }
+        list.set_power(machine, v);
+        return Ok(());
+    }
+
+    let target = parse_formula_items(reader, "an output like `2*铁板`")?;
+    if <Supply as Parsed>::first(reader.peek()?) {
+        if target.len() != 1 {
+            err_unknown_char(reader, "a single item before `<=`")?;
+        }
+        let item = target[0].item();
+        let v = <Supply as Parsed>::parse(reader)?.0;
+        parse_end(reader)?;
+        if list.supply.contains_key(&item) {
+            return Err(Error::DuplicateSupply(item, line));
+        }
+        list.supply.insert(item, v);
+        return Ok(());
+    }
+
+    let formula = Formula::parse_rest(reader, target)?;
+    parse_end(reader)?;
+    list.push(formula, line)
 }
 
 #[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "FormulaData")
+)]
 pub struct Formula {
-    target: FormulaItem,
+    target: Vec<FormulaItem>,
+    source: Vec<FormulaItem>,
+    speed: Fraction,
+    machine: Option<Rc<Machine>>,
+}
+
+/// [`Formula`] as deserialized, before it is checked like a parsed one.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct FormulaData {
+    target: Vec<FormulaItem>,
     source: Vec<FormulaItem>,
     speed: Fraction,
+    machine: Option<Rc<Machine>>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<FormulaData> for Formula {
+    type Error = String;
+
+    /// A formula has an output, and no zero amount or speed.
+    fn try_from(v: FormulaData) -> std::result::Result<Self, String> {
+        if v.target.is_empty() {
+            return Err("formula without output".to_string());
+        }
+        if let Some(x) = v.target.iter().chain(&v.source).find(|x| x.1.amount() == 0) {
+            return Err(format!("zero amount of {} in a formula", x.0));
+        }
+        if v.speed.amount() == 0 {
+            return Err("formula with zero speed".to_string());
+        }
+        let FormulaData {
+            target,
+            source,
+            speed,
+            machine,
+        } = v;
+        Ok(Formula {
+            target,
+            source,
+            speed,
+            machine,
+        })
+    }
 }
 
 impl Formula {
+    /// `target` starts with the main output, `speed` is in crafts per minute.
+    pub(crate) fn new(
+        target: Vec<FormulaItem>,
+        source: Vec<FormulaItem>,
+        speed: Fraction,
+        machine: Option<Rc<Machine>>,
+    ) -> Self {
+        Formula {
+            target,
+            source,
+            speed,
+            machine,
+        }
+    }
+
+    /// The main output, which is the first one declared.
     pub fn target(&self) -> &FormulaItem {
+        &self.target[0]
+    }
+
+    /// All outputs, the main output followed by byproducts.
+    pub fn targets(&self) -> &[FormulaItem] {
         &self.target
     }
 
+    /// The output of `item`, if this formula produces it.
+    pub fn output(&self, item: &Item) -> Option<&FormulaItem> {
+        self.target.iter().find(|x| *x.0 == *item)
+    }
+
     pub fn source(&self) -> &[FormulaItem] {
         &self.source
     }
@@ -107,35 +628,53 @@ impl Formula {
         self.speed
     }
 
-    fn fmt(&self, f: &mut Formatter<'_>, times: Fraction) -> std::fmt::Result {
-        let FormulaItem(target, amount) = &self.target;
-        let amount = times * amount.clone().into();
-        write!(f, "{target:?}*{amount:?} =")?;
+    /// The building running this formula, if declared.
+    pub fn machine(&self) -> Option<Rc<Machine>> {
+        self.machine.clone()
+    }
+
+    fn fmt<N: Number>(&self, f: &mut Formatter<'_>, times: N) -> std::fmt::Result {
+        for (i, v) in self.target.iter().enumerate() {
+            if i != 0 {
+                write!(f, " + ")?;
+            }
+            let FormulaItem(target, amount) = v;
+            let amount = times
+                .checked_mul(N::from_fraction(*amount))
+                .map_err(|_| std::fmt::Error)?;
+            write!(f, "{target:?}*{amount:?}")?;
+        }
+        write!(f, " =")?;
         for (i, v) in self.source.iter().enumerate() {
             if i != 0 {
                 write!(f, " +")?;
             }
             let FormulaItem(source, amount) = v;
-            let amount = times * amount.clone().into();
+            let amount = times
+                .checked_mul(N::from_fraction(*amount))
+                .map_err(|_| std::fmt::Error)?;
             write!(f, " {source:?}*{amount:?}")?;
         }
-        if times == 1.into() {
+        if times == N::from_fraction(1.into()) {
             let speed = self.speed;
             write!(f, " {speed:?}/min")?;
         }
+        if let Some(machine) = &self.machine {
+            write!(f, " @{machine}")?;
+        }
         Ok(())
     }
 }
 
 impl Debug for Formula {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        Formula::fmt(self, f, 1.into())
+        Formula::fmt(self, f, Fraction::from(1))
     }
 }
 
 impl Display for Formula {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        Formula::fmt(self, f, 1.into())
+        Formula::fmt(self, f, Fraction::from(1))
     }
 }
 
@@ -145,87 +684,118 @@ impl Parsed for Formula {
     }
 
     fn parse(reader: &mut impl ParserRead) -> Result<Formula> {
-        let target = <FormulaItem as Parsed>::parse(reader)?;
+        let target = parse_formula_items(reader, "an output like `2*铁板`")?;
+        Formula::parse_rest(reader, target)
+    }
+}
+
+impl Formula {
+    /// Parse what follows the outputs of a formula.
+    fn parse_rest(reader: &mut impl ParserRead, target: Vec<FormulaItem>) -> Result<Formula> {
         let mut source: Vec<FormulaItem> = Vec::new();
 
-        reader.skip_space()?;
         if reader.peek()? == '=' {
             _ = reader.read()?;
             reader.skip_space()?;
-            let c = reader.peek()?;
-            if !<FormulaItem as Parsed>::first(c) {
-                err_unknown_char(reader, "Formula-FormulaItem".to_string())?; // todo nameof()
-            }
-            let item = <FormulaItem as Parsed>::parse(reader)?;
-            source.push(item);
+            source = parse_formula_items(reader, "an input like `3*铁锭`")?;
+        }
 
-            loop {
-                reader.skip_space()?;
-                if reader.peek()? != '+' {
-                    break;
-                }
-                _ = reader.read()?;
-                reader.skip_space()?;
+        let c = reader.peek()?;
+        let speed = if <Rate as Parsed>::first(c) {
+            let rate = <Rate as Parsed>::parse(reader)?.0;
+            rate.checked_div(target[0].amount())?
+        } else {
+            if !<Speed as Parsed>::first(c) {
+                err_unknown_char(reader, "a time like `2s` or a rate like `@ 30/min`")?;
+            }
+            <Speed as Parsed>::parse(reader)?.0
+        };
 
-                let c = reader.peek()?;
-                if !<FormulaItem as Parsed>::first(c) {
-                    todo!("unknown char")
-                }
-                let item = <FormulaItem as Parsed>::parse(reader)?;
-                source.push(item);
+        let mut machine = None;
+        if is_space(reader.peek()?) {
+            reader.skip_space()?;
+            if <MachineName as Parsed>::first(reader.peek()?) {
+                machine = Some(<MachineName as Parsed>::parse(reader)?.0);
             }
         }
 
-        if !<Speed as Parsed>::first(reader.peek()?) {
-            err_unknown_char(reader, "Formula-Speed".to_string())?;
+        Ok(Formula::new(target, source, speed, machine))
+    }
+}
+
+/// Parse `formula_item *( *SP "+" *SP formula_item)` and the spaces after it.
+fn parse_formula_items(reader: &mut impl ParserRead, expected: &str) -> Result<Vec<FormulaItem>> {
+    let mut items: Vec<FormulaItem> = Vec::new();
+    loop {
+        let c = reader.peek()?;
+        if !<FormulaItem as Parsed>::first(c) {
+            err_unknown_char(reader, expected)?;
         }
-        let speed = <Speed as Parsed>::parse(reader)?.0;
+        let item = <FormulaItem as Parsed>::parse(reader)?;
+        items.push(item);
 
-        Ok(Formula {
-            target,
-            source,
-            speed,
-        })
+        reader.skip_space()?;
+        if reader.peek()? != '+' {
+            break;
+        }
+        _ = reader.read()?;
+        reader.skip_space()?;
     }
+    Ok(items)
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct FormulaItem(Rc<Item>, u32);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FormulaItem(Rc<Item>, Fraction);
 
 impl FormulaItem {
+    pub(crate) fn new(item: Rc<Item>, amount: Fraction) -> Self {
+        FormulaItem(item, amount)
+    }
+
     pub fn item(&self) -> Rc<Item> {
         self.0.clone()
     }
-    pub fn amount(&self) -> u32 {
+    pub fn amount(&self) -> Fraction {
         self.1
     }
 }
 
 impl Parsed for FormulaItem {
     fn first(c: char) -> bool {
-        c.is_alphabetic() || c.is_digit(10)
+        c.is_alphabetic() || c.is_ascii_digit()
     }
 
     fn parse(reader: &mut impl ParserRead) -> Result<Self> {
-        let mut amount = 1;
-        let mut name = String::new();
+        let mut amount = 1.into();
 
-        if reader.peek()?.is_digit(10) {
-            amount = reader.read()?.to_digit(10).unwrap();
-            while reader.peek()?.is_digit(10) {
-                amount = amount * 10 + reader.read()?.to_digit(10).unwrap();
-            }
+        if reader.peek()?.is_ascii_digit() {
+            amount = parse_number(reader)?;
             if reader.peek()? == '*' {
                 _ = reader.read()?;
             } else {
-                err_unknown_char(reader, "FormulaItem-Mid".to_string())?;
+                err_unknown_char(reader, "`*` after the amount")?;
             }
         }
 
-        if !reader.peek()?.is_alphabetic() {
-            todo!("unknown char")
+        if !<ItemName as Parsed>::first(reader.peek()?) {
+            err_unknown_char(reader, "an item name")?;
         }
+        let item = <ItemName as Parsed>::parse(reader)?.0;
+
+        Ok(FormulaItem::new(item, amount))
+    }
+}
 
+struct ItemName(Rc<Item>);
+
+impl Parsed for ItemName {
+    fn first(c: char) -> bool {
+        c.is_alphabetic()
+    }
+
+    fn parse(reader: &mut impl ParserRead) -> Result<Self> {
+        let mut name = String::new();
         name.push(reader.read()?);
         while reader.peek()?.is_alphanumeric() {
             name.push(reader.read()?);
@@ -235,22 +805,30 @@ impl Parsed for FormulaItem {
         let item = match item {
             Some(v) => v.clone(),
             None => {
-                let v = Rc::new(Item { name });
+                let v = Rc::new(Item::new(name));
                 reader.ctx().items.push(v.clone());
                 v
             }
         };
-
-        Ok(FormulaItem(item, amount))
+        Ok(ItemName(item))
     }
 }
 
 #[derive(PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 pub struct Item {
     name: String,
 }
 
 impl Item {
+    pub(crate) fn new(name: String) -> Self {
+        Item { name }
+    }
+
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let v = &self.name;
         write!(f, "{v}")?;
@@ -260,13 +838,365 @@ impl Item {
 
 impl Debug for Item {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        Item::fmt(&self, f)
+        Item::fmt(self, f)
     }
 }
 
 impl Display for Item {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        Item::fmt(&self, f)
+        Item::fmt(self, f)
+    }
+}
+
+/// A building type, e.g. `装配器`.
+#[derive(PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct Machine {
+    name: String,
+}
+
+impl Machine {
+    pub(crate) fn new(name: String) -> Self {
+        Machine { name }
+    }
+
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let v = &self.name;
+        write!(f, "{v}")?;
+        Ok(())
+    }
+}
+
+impl Debug for Machine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Machine::fmt(self, f)
+    }
+}
+
+impl Display for Machine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Machine::fmt(self, f)
+    }
+}
+
+struct MachineName(Rc<Machine>);
+
+impl Parsed for MachineName {
+    fn first(c: char) -> bool {
+        c == '@'
+    }
+
+    fn parse(reader: &mut impl ParserRead) -> Result<Self> {
+        _ = reader.read()?;
+        if !reader.peek()?.is_alphabetic() {
+            err_unknown_char(reader, "a machine name")?;
+        }
+
+        let mut name = String::new();
+        name.push(reader.read()?);
+        while reader.peek()?.is_alphanumeric() {
+            name.push(reader.read()?);
+        }
+
+        let machine = reader.ctx().machines.iter().find(|x| x.name == name);
+        let machine = match machine {
+            Some(v) => v.clone(),
+            None => {
+                let v = Rc::new(Machine::new(name));
+                reader.ctx().machines.push(v.clone());
+                v
+            }
+        };
+        Ok(MachineName(machine))
+    }
+}
+
+/// A way to move items between buildings, e.g. belts or pipes.
+#[derive(PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "TransportData")
+)]
+pub struct Transport {
+    name: String,
+    tiers: Vec<u32>,
+    items: Vec<Rc<Item>>,
+}
+
+/// [`Transport`] as deserialized, before it is checked like a parsed one.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct TransportData {
+    name: String,
+    tiers: Vec<u32>,
+    items: Vec<Rc<Item>>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<TransportData> for Transport {
+    type Error = String;
+
+    /// A transport has tiers, ascending from above zero.
+    fn try_from(v: TransportData) -> std::result::Result<Self, String> {
+        let TransportData { name, tiers, items } = v;
+        if tiers.is_empty() {
+            return Err(format!("transport {name} without tiers"));
+        }
+        if tiers[0] == 0 || tiers.windows(2).any(|x| x[0] >= x[1]) {
+            return Err(format!("tiers of transport {name} not ascending from above zero"));
+        }
+        Ok(Transport { name, tiers, items })
+    }
+}
+
+impl Transport {
+    /// Items per minute one line of each tier carries, ascending.
+    pub fn tiers(&self) -> &[u32] {
+        &self.tiers
+    }
+
+    /// Items carried, empty if it carries all items not listed by another transport.
+    pub fn items(&self) -> &[Rc<Item>] {
+        &self.items
+    }
+
+    /// Name of a tier counting from Mk1, e.g. `传送带Mk2` for tier 1.
+    pub fn tier_name(&self, tier: usize) -> String {
+        format!("{}Mk{}", self.name, tier + 1)
+    }
+
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let v = &self.name;
+        write!(f, "{v}")?;
+        Ok(())
+    }
+}
+
+impl Debug for Transport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Transport::fmt(self, f)
+    }
+}
+
+impl Display for Transport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Transport::fmt(self, f)
+    }
+}
+
+impl Parsed for Transport {
+    fn first(c: char) -> bool {
+        c == '~'
+    }
+
+    fn parse(reader: &mut impl ParserRead) -> Result<Self> {
+        _ = reader.read()?;
+        if !reader.peek()?.is_alphabetic() {
+            err_unknown_char(reader, "a transport name")?;
+        }
+        let mut name = String::new();
+        name.push(reader.read()?);
+        while reader.peek()?.is_alphanumeric() {
+            name.push(reader.read()?);
+        }
+
+        let mut tiers: Vec<u32> = Vec::new();
+        while is_space(reader.peek()?) {
+            reader.skip_space()?;
+            if !reader.peek()?.is_ascii_digit() {
+                break;
+            }
+            let (line, column) = reader.location();
+            let mut v = String::new();
+            while reader.peek()?.is_ascii_digit() {
+                v.push(reader.read()?);
+            }
+            let tier = parse_amount(v.clone(), line, column)?;
+            if tiers.last().is_some_and(|x| *x >= tier) {
+                return Err(Error::InvalidAmount(v, line, column, None));
+            }
+            tiers.push(tier);
+        }
+        if tiers.is_empty() {
+            err_unknown_char(reader, "the items per minute of a tier")?;
+        }
+
+        let mut items: Vec<Rc<Item>> = Vec::new();
+        if reader.peek()? == ':' {
+            _ = reader.read()?;
+            reader.skip_space()?;
+            loop {
+                if !<ItemName as Parsed>::first(reader.peek()?) {
+                    err_unknown_char(reader, "an item name")?;
+                }
+                items.push(<ItemName as Parsed>::parse(reader)?.0);
+
+                reader.skip_space()?;
+                if reader.peek()? != '+' {
+                    break;
+                }
+                _ = reader.read()?;
+                reader.skip_space()?;
+            }
+        }
+
+        Ok(Transport { name, tiers, items })
+    }
+}
+
+/// Parse the file at `path` into `list`, sharing the items and machines of `reader`.
+fn include(reader: &mut impl ParserRead, list: &mut FormulaList, path: String) -> Result<()> {
+    let ctx = reader.ctx();
+    let path = ctx.dir.join(path);
+    let wrap = |e: Error| Error::Include(path.clone(), Box::new(e));
+    let canonical = path.canonicalize().map_err(|e| wrap(e.into()))?;
+    if ctx.including.contains(&canonical) {
+        return Err(Error::IncludeCycle(canonical));
+    }
+    if ctx.included.contains(&canonical) {
+        return Ok(());
+    }
+    let file = File::open(&canonical).map_err(|e| wrap(e.into()))?;
+    ctx.included.push(canonical.clone());
+
+    let mut included = ParserReader::new(file);
+    included.ctx = ParseContext {
+        items: mem::take(&mut ctx.items),
+        machines: mem::take(&mut ctx.machines),
+        dir: canonical.parent().map(Path::to_path_buf).unwrap_or_default(),
+        including: ctx.including.clone(),
+        included: mem::take(&mut ctx.included),
+    };
+    included.ctx.including.push(canonical);
+    let mut diagnostics = Vec::new();
+    let r = parse_lines(&mut included, list, &mut diagnostics, &mut Vec::new());
+    ctx.items = included.ctx.items;
+    ctx.machines = included.ctx.machines;
+    ctx.included = included.ctx.included;
+    r.map_err(wrap)?;
+    match diagnostics.into_iter().next() {
+        Some(Diagnostic {
+            error: e @ Error::IncludeCycle(_),
+            ..
+        }) => Err(e),
+        Some(v) => Err(wrap(v.error)),
+        None => Ok(()),
+    }
+}
+
+/// Whether the line goes on with `include` and a quoted path, otherwise `include` is an item name.
+fn starts_include(reader: &impl ParserRead) -> bool {
+    let (_, column) = reader.location();
+    let line = reader.source_line();
+    let rest: String = line.chars().skip(column - 1).collect();
+    rest.strip_prefix("include")
+        .is_some_and(|x| x.starts_with(is_space) && x.trim_start_matches(is_space).starts_with('"'))
+}
+
+/// The path of `include "path"`.
+struct Include(String);
+
+impl Parsed for Include {
+    fn first(c: char) -> bool {
+        c == 'i'
+    }
+
+    fn parse(reader: &mut impl ParserRead) -> Result<Self> {
+        for c in "include".chars() {
+            if reader.peek()? != c {
+                err_unknown_char(reader, "`include`")?;
+            }
+            _ = reader.read()?;
+        }
+        reader.skip_space()?;
+        if reader.peek()? != '"' {
+            err_unknown_char(reader, "a quoted path like `\"common.txt\"`")?;
+        }
+        _ = reader.read()?;
+        let mut path = String::new();
+        while !matches!(reader.peek()?, '"' | '\n' | '\r' | '\0') {
+            path.push(reader.read()?);
+        }
+        if reader.peek()? != '"' || path.is_empty() {
+            err_unknown_char(reader, "a quoted path like `\"common.txt\"`")?;
+        }
+        _ = reader.read()?;
+        Ok(Include(path))
+    }
+}
+
+/// `<= n/min` after an item.
+struct Supply(Fraction);
+
+impl Parsed for Supply {
+    fn first(c: char) -> bool {
+        c == '<'
+    }
+
+    fn parse(reader: &mut impl ParserRead) -> Result<Self> {
+        _ = reader.read()?;
+        if reader.peek()? != '=' {
+            err_unknown_char(reader, "`<=`")?;
+        }
+        _ = reader.read()?;
+        reader.skip_space()?;
+
+        let (line, column) = reader.location();
+        let mut v = String::new();
+        while reader.peek()?.is_ascii_digit() {
+            v.push(reader.read()?);
+        }
+        if v.is_empty() {
+            err_unknown_char(reader, "the items per minute of the supply")?;
+        }
+        for unit in ['/', 'm', 'i', 'n'] {
+            if reader.peek()? != unit {
+                err_unknown_char(reader, "`/min`")?;
+            }
+            _ = reader.read()?;
+        }
+
+        let v = parse_amount(v, line, column)?;
+        Ok(Supply(v.into()))
+    }
+}
+
+struct MachinePower(Rc<Machine>, Fraction);
+
+impl Parsed for MachinePower {
+    fn first(c: char) -> bool {
+        <MachineName as Parsed>::first(c)
+    }
+
+    fn parse(reader: &mut impl ParserRead) -> Result<Self> {
+        let machine = <MachineName as Parsed>::parse(reader)?.0;
+        if !is_space(reader.peek()?) {
+            err_unknown_char(reader, "a space before the power")?;
+        }
+        reader.skip_space()?;
+
+        let (line, column) = reader.location();
+        let mut v = String::new();
+        while reader.peek()?.is_ascii_digit() {
+            v.push(reader.read()?);
+        }
+        if v.is_empty() {
+            err_unknown_char(reader, "the power in MW")?;
+        }
+        for unit in ['M', 'W'] {
+            if reader.peek()? != unit {
+                err_unknown_char(reader, "`MW`")?;
+            }
+            _ = reader.read()?;
+        }
+
+        let v = parse_amount(v, line, column)?;
+        Ok(MachinePower(machine, v.into()))
     }
 }
 
@@ -283,16 +1213,28 @@ where
 
 struct ParseContext {
     items: Vec<Rc<Item>>,
+    machines: Vec<Rc<Machine>>,
+    /// directory the included files are relative to
+    dir: PathBuf,
+    /// files being parsed, the outermost first
+    including: Vec<PathBuf>,
+    /// files included so far, a file reached again through another path is skipped
+    included: Vec<PathBuf>,
 }
 
 trait ParserRead {
     fn read(&mut self) -> Result<char>;
     fn peek(&mut self) -> Result<char>;
     fn ctx(&mut self) -> &mut ParseContext;
+    /// line and column of the next char, both counting from 1
     fn location(&self) -> (usize, usize);
+    /// whether the rest of the current line starts with `v`
+    fn starts_with(&mut self, v: &str) -> bool;
+    /// the current line without its line break
+    fn source_line(&self) -> String;
 
     fn skip_space(&mut self) -> Result<()> {
-        while self.peek()? == ' ' {
+        while is_space(self.peek()?) {
             _ = self.read()?;
         }
         Ok(())
@@ -316,7 +1258,13 @@ where
             buffer: Vec::<char>::new(),
             line: 0,
             column: 0,
-            ctx: ParseContext { items: Vec::new() },
+            ctx: ParseContext {
+                items: Vec::new(),
+                machines: Vec::new(),
+                dir: PathBuf::new(),
+                including: Vec::new(),
+                included: Vec::new(),
+            },
         }
     }
 }
@@ -341,7 +1289,7 @@ where
         self.column = 1;
         self.buffer = buf.chars().collect();
 
-        return Ok(self.buffer[0]);
+        Ok(self.buffer[0])
     }
 
     fn peek(&mut self) -> Result<char> {
@@ -358,7 +1306,7 @@ where
         self.column = 0;
         self.buffer = buf.chars().collect();
 
-        return Ok(self.buffer[0]);
+        Ok(self.buffer[0])
     }
 
     fn ctx(&mut self) -> &mut ParseContext {
@@ -366,14 +1314,105 @@ where
     }
 
     fn location(&self) -> (usize, usize) {
-        (self.line, self.column)
+        (self.line, self.column + 1)
+    }
+
+    fn starts_with(&mut self, v: &str) -> bool {
+        let rest = self.buffer.get(self.column..).unwrap_or_default();
+        rest.len() >= v.chars().count() && v.chars().zip(rest).all(|(l, r)| l == *r)
+    }
+
+    fn source_line(&self) -> String {
+        let v: String = self.buffer.iter().collect();
+        v.trim_end_matches(['\n', '\r']).to_string()
     }
 }
 
-fn err_unknown_char(reader: &mut impl ParserRead, target: String) -> Result<()> {
+/// The error of finding the next char instead of `expected`.
+fn err_unknown_char(reader: &mut impl ParserRead, expected: &str) -> Result<()> {
     let (line, column) = reader.location();
     let c = reader.peek()?;
-    Err(Error::UnknownChar(target, c, line, column))
+    if c == '\0' {
+        return Err(Error::UnexpectedEof(expected.to_string(), line, column));
+    }
+    Err(Error::UnknownChar(expected.to_string(), c, line, column))
+}
+
+/// A positive amount written at line, column.
+fn parse_amount(v: String, line: usize, column: usize) -> Result<u32> {
+    match v.parse::<u32>() {
+        Ok(0) => Err(Error::InvalidAmount(v, line, column, None)),
+        Ok(amount) => Ok(amount),
+        Err(e) => Err(Error::InvalidAmount(v, line, column, Some(e))),
+    }
+}
+
+/// A positive `1*DIGIT ["." 1*DIGIT] ["/" 1*DIGIT]`, converted exactly.
+fn parse_number(reader: &mut impl ParserRead) -> Result<Fraction> {
+    let (line, column) = reader.location();
+    let mut v = String::new();
+    for separator in [None, Some('.'), Some('/')] {
+        if let Some(c) = separator {
+            if reader.peek()? != c {
+                continue;
+            }
+            // a '/' without a digit after it starts the unit of a rate
+            if c == '/' && !('0'..='9').any(|d| reader.starts_with(&format!("/{d}"))) {
+                continue;
+            }
+            v.push(reader.read()?);
+        }
+        if !reader.peek()?.is_ascii_digit() {
+            err_unknown_char(reader, "a number like `2`, `1.5` or `3/2`")?;
+        }
+        while reader.peek()?.is_ascii_digit() {
+            v.push(reader.read()?);
+        }
+    }
+
+    let invalid = |e| Error::InvalidAmount(v.clone(), line, column, e);
+    let (decimal, unit) = v.split_once('/').unwrap_or((&v, "1"));
+    let (whole, part) = decimal.split_once('.').unwrap_or((decimal, ""));
+    let scale = 10u64
+        .checked_pow(part.len() as u32)
+        .ok_or_else(|| invalid(None))?;
+    let whole: u64 = whole.parse().map_err(|e| invalid(Some(e)))?;
+    let part: u64 = if part.is_empty() {
+        0
+    } else {
+        part.parse().map_err(|e| invalid(Some(e)))?
+    };
+    let unit: u64 = unit.parse().map_err(|e| invalid(Some(e)))?;
+    let amount = whole
+        .checked_mul(scale)
+        .and_then(|x| x.checked_add(part))
+        .ok_or_else(|| invalid(None))?;
+    let unit = scale.checked_mul(unit).ok_or_else(|| invalid(None))?;
+    if amount == 0 || unit == 0 {
+        return Err(invalid(None));
+    }
+    Ok(Fraction::new(amount, unit))
+}
+
+/// Spaces and tabs separate the parts of a line.
+fn is_space(c: char) -> bool {
+    c == ' ' || c == '\t'
+}
+
+/// `#` to the end of the line.
+struct Comment();
+
+impl Parsed for Comment {
+    fn first(c: char) -> bool {
+        c == '#'
+    }
+
+    fn parse(reader: &mut impl ParserRead) -> Result<Self> {
+        while !matches!(reader.peek()?, '\n' | '\r' | '\0') {
+            _ = reader.read()?;
+        }
+        Ok(Comment())
+    }
 }
 
 struct NewLine();
@@ -391,65 +1430,339 @@ impl Parsed for NewLine {
         }
 
         if c1 == '\r' {
+            _ = reader.read()?;
             if reader.peek()? == '\n' {
                 _ = reader.read()?;
                 return Ok(NewLine());
             }
         }
 
-        todo!("unknown char")
+        err_unknown_char(reader, "a line break").map(|_| NewLine())
     }
 }
 
+/// Crafts per minute of a duration like `2s`, `500ms` or `2m`.
 struct Speed(Fraction);
 
 impl Parsed for Speed {
     fn first(c: char) -> bool {
-        c.is_digit(10)
+        c.is_ascii_digit()
     }
 
     fn parse(reader: &mut impl ParserRead) -> Result<Self> {
-        let mut v = String::new();
-        loop {
-            let c = reader.read()?;
-            if c == 's' {
-                break;
+        let (line, column) = reader.location();
+        let v = parse_number(reader)?;
+        let minutes = if reader.peek()? == 'm' {
+            _ = reader.read()?;
+            if reader.peek()? == 's' {
+                Fraction::new(1, 60_000)
+            } else {
+                1.into()
             }
-
-            if c.is_digit(10) {
-                v.push(c);
-                continue;
+        } else {
+            if reader.peek()? != 's' {
+                err_unknown_char(reader, "`ms`, `s` or `m` after the time")?;
             }
-            todo!("unknown char");
+            Fraction::new(1, 60)
+        };
+        if reader.peek()? == 's' {
+            _ = reader.read()?;
+        }
+
+        let speed = v
+            .checked_mul(minutes)
+            .and_then(|x| Fraction::from(1).checked_div(x))
+            .map_err(|_| Error::InvalidAmount(format!("{v:?}"), line, column, None))?;
+        Ok(Speed(speed))
+    }
+}
+
+const RATE_UNIT: &str = "`/s`, `/min` or `/h` after the rate";
+
+/// Items of the main output per minute of `@ 30/min`, `@ 0.5/s` or `@ 1800/h`.
+struct Rate(Fraction);
+
+impl Parsed for Rate {
+    fn first(c: char) -> bool {
+        c == '@'
+    }
+
+    fn parse(reader: &mut impl ParserRead) -> Result<Self> {
+        _ = reader.read()?;
+        reader.skip_space()?;
+        let v = parse_number(reader)?;
+        if reader.peek()? != '/' {
+            err_unknown_char(reader, RATE_UNIT)?;
         }
+        _ = reader.read()?;
 
-        let v = v.parse::<u32>()?;
-        let time: Fraction = 60.into();
-        Ok(Speed(time / v.into()))
+        let (line, column) = reader.location();
+        let c = reader.peek()?;
+        let mut unit = String::new();
+        while reader.peek()?.is_ascii_alphabetic() {
+            unit.push(reader.read()?);
+        }
+        let unit = match unit.as_str() {
+            "s" => RateUnit::Second,
+            "min" => RateUnit::Minute,
+            "h" => RateUnit::Hour,
+            _ if c == '\0' => return Err(Error::UnexpectedEof(RATE_UNIT.to_string(), line, column)),
+            _ => return Err(Error::UnknownChar(RATE_UNIT.to_string(), c, line, column)),
+        };
+        Ok(Rate(v.checked_div(unit.minutes())?))
     }
 }
 
 #[derive(Debug)]
-pub struct Plan {
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "N: serde::Serialize, N::Signed: serde::Serialize",
+        deserialize = "N: serde::Deserialize<'de>, N::Signed: serde::Deserialize<'de>"
+    ))
+)]
+pub struct Plan<N: Number = Fraction> {
     pub formula: Rc<Formula>,
-    pub speed: Fraction,
-    pub group: Fraction,
-    pub rate: Fraction,
+    /// index of `formula` in [`FormulaList::recipes`] of the item it is selected for
+    pub recipe: usize,
+    /// the output `speed` is of, the main output unless the plan stands for a source item
+    pub item: Rc<Item>,
+    /// speed of `item`
+    pub speed: N,
+    pub group: N,
+    pub rate: N,
+    /// clock speed of every building, 1 is 100%
+    pub clock: N,
+    /// power in MW of all buildings, zero if the machine has no declared power
+    pub power: N,
+    /// production minus consumption per minute of the outputs not exactly used up,
+    /// positive for a surplus and negative for a shortfall
+    pub balance: Vec<(Rc<Item>, N::Signed)>,
+    /// unit of the rates displayed, the fields are always per minute
+    pub unit: RateUnit,
+}
+
+impl<N: Number> Plan<N> {
+    /// Plan running `formula` `crafts` times per minute in total.
+    pub(crate) fn new(
+        meta: &FormulaList,
+        formula: Rc<Formula>,
+        recipe: usize,
+        crafts: N,
+    ) -> Result<Plan<N>> {
+        let item = formula.target().item();
+        Plan::supplying(meta, formula, recipe, item, crafts)
+    }
+
+    /// [`Plan::new`] with the speed of the output `item`, e.g. the source item it stands for.
+    pub(crate) fn supplying(
+        meta: &FormulaList,
+        formula: Rc<Formula>,
+        recipe: usize,
+        item: Rc<Item>,
+        crafts: N,
+    ) -> Result<Plan<N>> {
+        let group = crafts.checked_div(N::from_fraction(formula.speed()))?;
+        let amount = N::from_fraction(output_of(&formula, &item)?);
+        // buildings running below full rate draw power in proportion
+        let power = match formula.machine().and_then(|x| meta.power(&x)) {
+            Some(v) => group.checked_mul(N::from_fraction(v))?,
+            None => N::zero(),
+        };
+        Ok(Plan {
+            speed: crafts.checked_mul(amount)?,
+            group: group.ceil(),
+            rate: group.checked_div(group.ceil())?,
+            clock: N::from_fraction(1.into()),
+            power,
+            formula,
+            recipe,
+            item,
+            balance: Vec::new(),
+            unit: RateUnit::Minute,
+        })
+    }
+
+    /// Crafts per minute of the formula.
+    pub fn crafts(&self) -> Result<N> {
+        crafts_of(&self.formula, &self.item, self.speed)
+    }
+}
+
+/// Crafts per minute of `formula` making `item` at `speed`.
+fn crafts_of<N: Number>(formula: &Formula, item: &Rc<Item>, speed: N) -> Result<N> {
+    speed.checked_div(N::from_fraction(output_of(formula, item)?))
+}
+
+/// Amount of `item` a craft of `formula` makes.
+fn output_of(formula: &Formula, item: &Rc<Item>) -> Result<Fraction> {
+    let output = formula
+        .output(item)
+        .ok_or_else(|| Error::MissingRecipe(item.clone()))?;
+    Ok(output.amount())
+}
+
+impl<N: Number> Plan<N> {
+    /// Run the fewest buildings at the same clock, at most `max` percent,
+    /// with power growing by [`POWER_EXPONENT`] of the clock.
+    pub(crate) fn overclock(mut self, meta: &FormulaList, max: u32) -> Result<Plan<N>> {
+        let group = self
+            .crafts()?
+            .checked_div(N::from_fraction(self.formula.speed()))?;
+        let buildings = group
+            .checked_div(N::from_fraction(Fraction::new(max.into(), 100)))?
+            .ceil();
+        if buildings == N::zero() {
+            return Ok(self);
+        }
+        self.group = buildings;
+        self.rate = N::from_fraction(1.into());
+        self.clock = group.checked_div(buildings)?;
+        self.power = match self.formula.machine().and_then(|x| meta.power(&x)) {
+            Some(v) => buildings
+                .checked_mul(N::from_fraction(v))?
+                .checked_mul(self.clock.powf(POWER_EXPONENT)?)?,
+            None => N::zero(),
+        };
+        Ok(self)
+    }
+}
+
+/// power of a building is its power at 100% times the clock raised to this
+pub const POWER_EXPONENT: f64 = 1.321928;
+
+/// Unit of the rates a [`Plan`] or a [`Flow`](crate::logistics::Flow) displays.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RateUnit {
+    Second,
+    #[default]
+    Minute,
+    Hour,
+}
+
+impl RateUnit {
+    /// Minutes in one unit.
+    pub fn minutes(self) -> Fraction {
+        match self {
+            RateUnit::Second => Fraction::new(1, 60),
+            RateUnit::Minute => 1.into(),
+            RateUnit::Hour => 60.into(),
+        }
+    }
+}
+
+impl Display for RateUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RateUnit::Second => write!(f, "s"),
+            RateUnit::Minute => write!(f, "min"),
+            RateUnit::Hour => write!(f, "h"),
+        }
+    }
 }
 
-impl Display for Plan {
+impl<N: Number> Display for Plan<N> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let Plan {
             formula,
+            recipe,
+            item: output,
             speed,
             group,
             rate,
+            clock,
+            power,
+            balance,
+            unit,
         } = self;
-        let rate = *rate * 100.into();
-        write!(
-            f,
-            "group={group:2} speed={speed:6.2}/min rate={rate:6.2}% ==> "
-        )?;
-        Formula::fmt(formula, f, *speed / formula.target.amount().into())
+        let minutes = unit.minutes();
+        let speed = speed
+            .checked_mul(N::from_fraction(minutes))
+            .map_err(|_| std::fmt::Error)?;
+        let rate = rate
+            .checked_mul(N::from_fraction(100.into()))
+            .map_err(|_| std::fmt::Error)?;
+        write!(f, "group={group:2} speed={speed:6.2}/{unit} rate={rate:6.2}%")?;
+        if *clock != N::from_fraction(1.into()) {
+            let clock = clock
+                .checked_mul(N::from_fraction(100.into()))
+                .map_err(|_| std::fmt::Error)?;
+            write!(f, " clock={clock:6.2}%")?;
+        }
+        if *power != N::zero() {
+            write!(f, " power={power:7.2}MW")?;
+        }
+        write!(f, " ==> ")?;
+        if *recipe != 0 {
+            write!(f, "[alt {recipe}] ")?;
+        }
+        let times = crafts_of(formula, output, speed).map_err(|_| std::fmt::Error)?;
+        Formula::fmt(formula, f, times)?;
+        for (item, v) in balance.iter() {
+            let v = v
+                .checked_mul(N::Signed::from_unsigned(minutes))
+                .map_err(|_| std::fmt::Error)?;
+            if v.is_negative() {
+                write!(f, " (shortfall {item}*{:?}/{unit})", -v)?;
+            } else {
+                write!(f, " (surplus {item}*{v:?}/{unit})")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Buildings needed by a list of plans, per [`Machine`].
+#[derive(Debug)]
+pub struct Buildings<N: Number = Fraction> {
+    /// buildings of every machine in the order they are first used,
+    /// `None` counts the formulas without a machine
+    pub machines: Vec<(Option<Rc<Machine>>, N)>,
+    pub total: N,
+    /// power in MW of all plans
+    pub power: N,
+}
+
+impl<N: Number> Buildings<N> {
+    pub fn new(plans: &[Plan<N>]) -> Result<Buildings<N>> {
+        let mut machines: Vec<(Option<Rc<Machine>>, N)> = Vec::new();
+        let mut total = N::zero();
+        let mut power = N::zero();
+        for plan in plans {
+            let machine = plan.formula.machine();
+            let i = match machines.iter().position(|(x, _)| *x == machine) {
+                Some(i) => i,
+                None => {
+                    machines.push((machine, N::zero()));
+                    machines.len() - 1
+                }
+            };
+            machines[i].1 = machines[i].1.checked_add(plan.group)?;
+            total = total.checked_add(plan.group)?;
+            power = power.checked_add(plan.power)?;
+        }
+        Ok(Buildings {
+            machines,
+            total,
+            power,
+        })
+    }
+}
+
+impl<N: Number> Display for Buildings<N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (machine, count) in self.machines.iter() {
+            match machine {
+                Some(machine) => writeln!(f, "{machine}: {count}")?,
+                None => writeln!(f, "(no machine): {count}")?,
+            }
+        }
+        write!(f, "total: {}", self.total)?;
+        if self.power != N::zero() {
+            write!(f, "\npower: {}MW", self.power)?;
+        }
+        Ok(())
     }
 }
@@ -30,32 +30,39 @@ fn test_list_from_reader() {
 
     let formula_list = FormulaList {
         items: items.clone(),
+        machines: vec![],
         formulas: vec![
             Rc::new(Formula {
-                target: FormulaItem(get_item(&items, "智能护板"), 1),
+                target: vec![FormulaItem(get_item(&items, "智能护板"), 1.into())],
                 source: vec![
-                    FormulaItem(get_item(&items, "加强铁板"), 1),
-                    FormulaItem(get_item(&items, "转子"), 1),
+                    FormulaItem(get_item(&items, "加强铁板"), 1.into()),
+                    FormulaItem(get_item(&items, "转子"), 1.into()),
                 ],
                 speed: 2.into(),
+                machine: None,
             }),
             Rc::new(Formula {
-                target: FormulaItem(get_item(&items, "转子"), 1),
+                target: vec![FormulaItem(get_item(&items, "转子"), 1.into())],
                 source: vec![
-                    FormulaItem(get_item(&items, "铁棒"), 5),
-                    FormulaItem(get_item(&items, "螺丝"), 25),
+                    FormulaItem(get_item(&items, "铁棒"), 5.into()),
+                    FormulaItem(get_item(&items, "螺丝"), 25.into()),
                 ],
                 speed: 4.into(),
+                machine: None,
             }),
             Rc::new(Formula {
-                target: FormulaItem(get_item(&items, "加强铁板"), 1),
+                target: vec![FormulaItem(get_item(&items, "加强铁板"), 1.into())],
                 source: vec![
-                    FormulaItem(get_item(&items, "铁板"), 6),
-                    FormulaItem(get_item(&items, "螺丝"), 12),
+                    FormulaItem(get_item(&items, "铁板"), 6.into()),
+                    FormulaItem(get_item(&items, "螺丝"), 12.into()),
                 ],
                 speed: 5.into(),
+                machine: None,
             }),
         ],
+        power: HashMap::new(),
+        transports: vec![],
+        supply: HashMap::new(),
     };
 
     let output = FormulaList::from_reader(input.as_bytes()).unwrap();
@@ -70,3 +77,391 @@ fn get_item(items: &Vec<Rc<Item>>, name: &str) -> Rc<Item> {
     }
     panic!("not found item {}", name)
 }
+
+#[test]
+fn test_byproduct_from_reader() {
+    let input = "2*塑料 + 1*重油残渣 = 3*原油 6s";
+    let output = FormulaList::from_reader(input.as_bytes()).unwrap();
+    let items = output.items().to_vec();
+
+    let formula = Formula {
+        target: vec![
+            FormulaItem(get_item(&items, "塑料"), 2.into()),
+            FormulaItem(get_item(&items, "重油残渣"), 1.into()),
+        ],
+        source: vec![FormulaItem(get_item(&items, "原油"), 3.into())],
+        speed: 10.into(),
+        machine: None,
+    };
+    assert_eq!(output.formulas(), &[Rc::new(formula)]);
+    assert_eq!(output.recipes(&get_item(&items, "重油残渣")).len(), 1);
+    assert_eq!(
+        output.formulas()[0].to_string(),
+        "塑料*2 + 重油残渣*1 = 原油*3 10/min"
+    );
+}
+
+#[test]
+fn test_machine_from_reader() {
+    let input = "铁锭 = 铁矿石 2s @冶炼炉\n铁棒 = 铁锭 4s  @构筑站\n钢锭 = 铁矿石 4s @冶炼炉";
+    let output = FormulaList::from_reader(input.as_bytes()).unwrap();
+    let machines = output.machines();
+    assert_eq!(machines.len(), 2);
+    assert_eq!(machines[0].to_string(), "冶炼炉");
+    assert_eq!(output.formulas()[0].machine(), Some(machines[0].clone()));
+    assert_eq!(output.formulas()[2].machine(), Some(machines[0].clone()));
+    assert_eq!(output.formulas()[1].to_string(), "铁棒*1 = 铁锭*1 15/min @构筑站");
+
+    assert_eq!(output.power(&machines[0]), None);
+
+    let output = FormulaList::from_reader("@冶炼炉 4MW\n铁锭 = 铁矿石 2s @冶炼炉".as_bytes()).unwrap();
+    assert_eq!(output.machines().len(), 1);
+    assert_eq!(output.power(&output.machines()[0]), Some(4.into()));
+
+    let parse = |input: &str| FormulaList::from_reader(input.as_bytes()).unwrap_err();
+    assert!(matches!(parse("@冶炼炉 4MW\n@冶炼炉 5MW"), Error::DuplicateMachine(_, 2)));
+    assert!(matches!(parse("@冶炼炉 4kW"), Error::UnknownChar(_, 'k', 1, _)));
+    assert!(matches!(parse("@冶炼炉 MW"), Error::UnknownChar(_, 'M', 1, _)));
+    assert!(matches!(parse("铁锭 = 铁矿石 2s @"), Error::UnexpectedEof(_, 1, _)));
+    assert!(matches!(parse("铁锭 = 铁矿石 2s 冶炼炉"), Error::UnknownChar(_, '冶', 1, _)));
+    assert!(matches!(parse("铁锭 = 铁矿石 2s@冶炼炉"), Error::UnknownChar(_, '@', 1, _)));
+}
+
+#[test]
+fn test_transport_from_reader() {
+    let input = "~传送带 60 120\n~管道 300 600: 水 + 原油\n塑料 = 原油 + 水 6s";
+    let output = FormulaList::from_reader(input.as_bytes()).unwrap();
+    let transports = output.transports();
+    assert_eq!(transports.len(), 2);
+    assert_eq!(transports[0].tiers(), &[60, 120]);
+    assert_eq!(transports[1].tier_name(1), "管道Mk2");
+    let transport = |name: &str| output.transport(&output.get_item(name).unwrap()).unwrap();
+    assert_eq!(transport("水"), transports[1]);
+    assert_eq!(transport("塑料"), transports[0]);
+
+    let output = FormulaList::from_reader("~管道 300: 水\n塑料 = 水 6s".as_bytes()).unwrap();
+    assert_eq!(output.transport(&output.get_item("塑料").unwrap()), None);
+
+    let parse = |input: &str| FormulaList::from_reader(input.as_bytes()).unwrap_err();
+    assert!(matches!(parse("~传送带 60\n~传送带 120"), Error::DuplicateTransport(_, 2)));
+    assert!(matches!(parse("~传送带 120 60"), Error::InvalidAmount(_, 1, _, None)));
+    assert!(matches!(parse("~传送带: 水"), Error::UnknownChar(_, ':', 1, _)));
+    assert!(matches!(parse("~传送带 60 x"), Error::UnknownChar(_, 'x', 1, _)));
+    assert!(matches!(parse("~管道 300: 2*水"), Error::UnknownChar(_, '2', 1, _)));
+}
+
+#[test]
+fn test_supply_from_reader() {
+    let input = "铁矿石 <= 480/min\n铁矿石 1s\n铜矿石 1s\n铜矿石<=60/min";
+    let output = FormulaList::from_reader(input.as_bytes()).unwrap();
+    assert_eq!(output.formulas().len(), 2);
+    assert_eq!(output.limited().len(), 2);
+    assert_eq!(output.supply(&output.get_item("铁矿石").unwrap()), Some(480.into()));
+    assert_eq!(output.supply(&output.get_item("铜矿石").unwrap()), Some(60.into()));
+
+    let parse = |input: &str| FormulaList::from_reader(input.as_bytes()).unwrap_err();
+    assert!(matches!(parse("铁矿石 <= 1/min\n铁矿石 <= 2/min"), Error::DuplicateSupply(_, 2)));
+    assert!(matches!(parse("铁矿石 + 铜矿石 <= 1/min"), Error::UnknownChar(_, '<', 1, _)));
+    assert!(matches!(parse("铁矿石 < 1/min"), Error::UnknownChar(_, ' ', 1, _)));
+    assert!(matches!(parse("铁矿石 <= 1/s"), Error::UnknownChar(_, 's', 1, _)));
+    assert!(matches!(parse("铁矿石 <= 0/min"), Error::InvalidAmount(_, 1, _, None)));
+}
+
+#[test]
+fn test_parse_error() {
+    let parse = |input: &str| FormulaList::from_reader(input.as_bytes()).unwrap_err();
+
+    assert!(matches!(parse("铁锭 = 铁矿石 2x"), Error::UnknownChar(_, 'x', 1, _)));
+    assert!(matches!(parse("铁锭 = 铁矿石 2"), Error::UnexpectedEof(_, 1, _)));
+    assert!(matches!(parse("铁锭 = 铁矿石"), Error::UnexpectedEof(_, 1, _)));
+    assert!(matches!(parse("铁锭 = 铁矿石 0s"), Error::InvalidAmount(_, 1, _, None)));
+    assert!(matches!(
+        parse("铁锭 = 99999999999999999999*铁矿石 2s"),
+        Error::InvalidAmount(_, 1, _, Some(_))
+    ));
+    assert!(matches!(
+        parse("铁矿石 1s\n铁锭 = 铁矿石 2s\n铁锭 = 铁矿石 2s"),
+        Error::DuplicateRecipe(_, 3)
+    ));
+    assert!(matches!(parse("铁矿石 1s\r铁锭 = 铁矿石 2s"), Error::UnknownChar(_, '铁', 1, _)));
+
+    let output = FormulaList::from_reader("铁矿石 1s\r\n铁锭 = 铁矿石 2s\r\n".as_bytes()).unwrap();
+    assert_eq!(output.formulas().len(), 2);
+    assert!(matches!(output.get_item("铁棒"), Err(Error::UnknownItem(_))));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde() {
+    let input = "铁矿石 1s @矿机\n铁锭 = 铁矿石 2s @冶炼炉\n@冶炼炉 4MW\n~传送带 60 120\n铁矿石 <= 480/min";
+    let list = FormulaList::from_reader(input.as_bytes()).unwrap();
+    let json = serde_json::to_string(&list).unwrap();
+    assert!(json.contains(r#""target":[["铁锭","1/1"]],"source":[["铁矿石","1/1"]],"speed":"30/1","machine":"冶炼炉""#));
+
+    let output: FormulaList = serde_json::from_str(&json).unwrap();
+    assert_eq!(output, list);
+    let ore = output.get_item("铁矿石").unwrap();
+    assert!(Rc::ptr_eq(&output.formulas()[1].source()[0].item(), &ore));
+    assert!(Rc::ptr_eq(&output.formulas()[1].machine().unwrap(), &output.machines()[1]));
+
+    let target = list.get_item("铁锭").unwrap();
+    let plans = crate::calculate(&list, &[], target, 45, &Default::default()).unwrap();
+    let json = serde_json::to_string(&plans[0]).unwrap();
+    assert!(json.contains(r#""speed":"45/1","group":"1/1","rate":"3/4""#));
+    let plan: Plan = serde_json::from_str(&json).unwrap();
+    assert_eq!(plan.to_string(), plans[0].to_string());
+
+    assert!(serde_json::from_str::<Fraction>(r#""1/0""#).is_err());
+    assert_eq!(serde_json::from_str::<Fraction>(r#""6""#).unwrap(), 6.into());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_invalid() {
+    let input = "铁矿石 1s\n铁锭 = 铁矿石 2s @冶炼炉\n@冶炼炉 4MW\n~传送带 60 120\n铁矿石 <= 480/min";
+    let list = FormulaList::from_reader(input.as_bytes()).unwrap();
+    let json = serde_json::to_string(&list).unwrap();
+    let parse = |from: &str, to: &str| {
+        assert!(json.contains(from), "{from}");
+        serde_json::from_str::<FormulaList>(&json.replacen(from, to, 1))
+            .unwrap_err()
+            .to_string()
+    };
+
+    let e = parse(r#""target":[["铁锭","1/1"]]"#, r#""target":[]"#);
+    assert!(e.starts_with("formula without output"), "{e}");
+    let e = parse(r#"["铁矿石","1/1"]]"#, r#"["铁矿石","0/1"]]"#);
+    assert!(e.starts_with("zero amount of 铁矿石"), "{e}");
+    let e = parse(r#""speed":"30/1""#, r#""speed":"0""#);
+    assert!(e.starts_with("formula with zero speed"), "{e}");
+    let e = parse(r#""tiers":[60,120]"#, r#""tiers":[]"#);
+    assert!(e.starts_with("transport 传送带 without tiers"), "{e}");
+    let e = parse(r#""tiers":[60,120]"#, r#""tiers":[120,60]"#);
+    assert!(e.starts_with("tiers of transport 传送带 not ascending"), "{e}");
+    let e = parse(r#""tiers":[60,120]"#, r#""tiers":[0,60]"#);
+    assert!(e.starts_with("tiers of transport 传送带 not ascending"), "{e}");
+    let e = parse(r#""480/1""#, r#""0""#);
+    assert!(e.starts_with("zero supply of 铁矿石"), "{e}");
+    let e = parse(r#""冶炼炉":"4/1""#, r#""冶炼炉":"0""#);
+    assert!(e.starts_with("zero power of machine 冶炼炉"), "{e}");
+
+    let min = r#""-170141183460469231731687303715884105728/1""#;
+    assert!(serde_json::from_str::<crate::Rational>(min).is_err());
+    assert!(serde_json::from_str::<crate::Rational>(r#""1/0""#).is_err());
+
+    let formulas = r#""formulas":["#;
+    let start = json.find(formulas).unwrap() + formulas.len();
+    let end = start + json[start..].find(r#"},{"#).unwrap() + 1;
+    let formula = &json[start..end];
+    let e = parse(formulas, &format!("{formulas}{formula},"));
+    assert!(e.starts_with("duplicate recipe 铁矿石*1 = 60/min"), "{e}");
+}
+
+/// A directory of one test, removed with everything in it when dropped.
+struct TempDir(std::path::PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("planner_{name}_{}", std::process::id()));
+        _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        TempDir(dir)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn test_include() {
+    let temp = TempDir::new("include");
+    let dir = &temp.0;
+    std::fs::create_dir_all(dir.join("mods")).unwrap();
+    let write = |name: &str, v: &str| std::fs::write(dir.join(name), v).unwrap();
+    write("base.txt", "铁矿石 1s\ninclude \"mods/alternate.txt\"\n铁棒 = 铁锭 4s");
+    write("mods/alternate.txt", "铁锭 = 铁矿石 2s\ninclude \"more.txt\"");
+    write("mods/more.txt", "铁锭 = 3*铁矿石 3s");
+
+    let output = FormulaList::from_path(dir.join("base.txt")).unwrap();
+    let formulas: Vec<_> = output.formulas().iter().map(|x| x.to_string()).collect();
+    assert_eq!(formulas[1..], ["铁锭*1 = 铁矿石*1 30/min", "铁锭*1 = 铁矿石*3 20/min", "铁棒*1 = 铁锭*1 15/min"]);
+    assert_eq!(output.items().len(), 3);
+    let ingot = output.get_item("铁锭").unwrap();
+    assert!(Rc::ptr_eq(&output.formulas()[3].source()[0].item(), &ingot));
+
+    // errors of an included file name it
+    write("mods/more.txt", "铁锭 = 铁矿石 2s");
+    let e = FormulaList::from_path(dir.join("base.txt")).unwrap_err();
+    assert!(matches!(&e, Error::Include(_, e) if matches!(e.as_ref(), Error::Include(path, e)
+        if path.ends_with("more.txt") && matches!(e.as_ref(), Error::DuplicateRecipe(_, 1)))));
+
+    write("mods/more.txt", "include \"../base.txt\"");
+    let e = FormulaList::from_path(dir.join("base.txt")).unwrap_err();
+    assert!(matches!(e, Error::IncludeCycle(path) if path.ends_with("base.txt")));
+
+    write("mods/more.txt", "include \"missing.txt\"");
+    let e = FormulaList::from_path(dir.join("base.txt")).unwrap_err();
+    assert!(e.to_string().contains("missing.txt"));
+
+    // a file reached through two includes is read once
+    write("main.txt", "include \"alt.txt\"\ninclude \"mods/mod.txt\"");
+    write("alt.txt", "include \"common.txt\"\n铁锭 = 铁矿石 2s");
+    write("mods/mod.txt", "include \"../common.txt\"\n铁棒 = 铁锭 4s");
+    write("common.txt", "铁矿石 1s");
+    let output = FormulaList::from_path(dir.join("main.txt")).unwrap();
+    assert_eq!(output.formulas().len(), 3);
+
+    // the error of an included file has the line in that file only
+    write("common.txt", "铁矿石 1s\n铁矿石 1s");
+    let (_, diagnostics) = FormulaList::parse_path(dir.join("alt.txt")).unwrap();
+    let e = diagnostics[0].to_string();
+    assert!(e.ends_with("common.txt: Duplicate recipe 铁矿石*1 = 60/min at line 2"), "{e}");
+
+    let parse = |input: &str| FormulaList::from_reader(input.as_bytes()).unwrap_err();
+    assert!(matches!(parse("include base.txt"), Error::UnknownChar(_, 'b', 1, _)));
+    assert!(matches!(parse("include \"base.txt"), Error::UnexpectedEof(_, 1, _)));
+    // without a quoted path `include` is an item
+    let output = FormulaList::from_reader("include 1s\n铁锭 = include 2s".as_bytes()).unwrap();
+    assert_eq!(output.items()[0].to_string(), "include");
+    let output = FormulaList::from_reader("includes 1s".as_bytes()).unwrap();
+    assert_eq!(output.items()[0].to_string(), "includes");
+}
+
+#[test]
+fn test_merge() {
+    let parse = |input: &str| FormulaList::from_reader(input.as_bytes()).unwrap();
+    let mut list = parse("铁矿石 1s\n铁锭 = 铁矿石 2s @冶炼炉\n@冶炼炉 4MW");
+    let other = parse("铁锭 = 3*铁矿石 3s @冶炼炉\n@冶炼炉 4MW\n铁矿石 <= 60/min");
+    list.merge(&other).unwrap();
+    assert_eq!(list.formulas().len(), 3);
+    assert_eq!(list.items().len(), 2);
+    assert_eq!(list.machines().len(), 1);
+    let ore = list.get_item("铁矿石").unwrap();
+    assert!(Rc::ptr_eq(&list.formulas()[2].source()[0].item(), &ore));
+    assert!(Rc::ptr_eq(&list.formulas()[2].machine().unwrap(), &list.machines()[0]));
+    assert_eq!(list.supply(&ore), Some(60.into()));
+
+    let before = list.clone();
+    let other = parse("铁锭 = 铁矿石 2s @冶炼炉\n@冶炼炉 5MW\n铜矿石 1s");
+    let e = list.merge(&other).unwrap_err();
+    assert!(matches!(e, Error::MergeConflict(v) if v.len() == 2));
+    assert_eq!(list, before);
+}
+
+#[test]
+fn test_comment_and_blank() {
+    let input = "# 基础配方\n\n  铁矿石 1s   # 矿机\n\t铁锭\t=\t铁矿石\t2s\t@冶炼炉 \r\n\n# 电力\n@冶炼炉\t4MW\t\n~传送带 60 120 \n铁矿石 <= 480/min# 限制\n#";
+    let output = FormulaList::from_reader(input.as_bytes()).unwrap();
+    let expect = FormulaList::from_reader(
+        "铁矿石 1s\n铁锭 = 铁矿石 2s @冶炼炉\n@冶炼炉 4MW\n~传送带 60 120\n铁矿石 <= 480/min".as_bytes(),
+    )
+    .unwrap();
+    assert_eq!(output, expect);
+    assert_eq!(FormulaList::from_reader("".as_bytes()).unwrap().formulas().len(), 0);
+    assert_eq!(FormulaList::from_reader("\n# 空\n".as_bytes()).unwrap().formulas().len(), 0);
+
+    let parse = |input: &str| FormulaList::from_reader(input.as_bytes()).unwrap_err();
+    assert!(matches!(parse("铁矿石 1s\n\n铁锭 = 铁矿石 2x"), Error::UnknownChar(_, 'x', 3, _)));
+    assert!(matches!(parse("铁矿石 1s 铁锭"), Error::UnknownChar(_, '铁', 1, _)));
+    assert!(matches!(parse("铁锭 = # 铁矿石 2s"), Error::UnknownChar(_, '#', 1, _)));
+}
+
+#[test]
+fn test_fraction_from_reader() {
+    let input = "燃料 = 1.5*水 + 3/2*原油 0.5s\n橡胶 = 2*原油 4/3s\n塑料 = 原油 1.2s";
+    let output = FormulaList::from_reader(input.as_bytes()).unwrap();
+    let formulas = output.formulas();
+    assert_eq!(formulas[0].source()[0].amount(), Fraction::new(3, 2));
+    assert_eq!(formulas[0].source()[1].amount(), Fraction::new(3, 2));
+    assert_eq!(formulas[0].speed(), 120.into());
+    assert_eq!(formulas[1].speed(), 45.into());
+    assert_eq!(formulas[2].speed(), 50.into());
+    assert_eq!(formulas[0].to_string(), "燃料*1 = 水*3/2 + 原油*3/2 120/min");
+
+    let parse = |input: &str| FormulaList::from_reader(input.as_bytes()).unwrap_err();
+    assert!(matches!(parse("燃料 = 0.0*水 1s"), Error::InvalidAmount(_, 1, _, None)));
+    assert!(matches!(parse("燃料 = 水 3/0s"), Error::InvalidAmount(_, 1, _, None)));
+    assert!(matches!(parse("燃料 = 1.*水 1s"), Error::UnknownChar(_, '*', 1, _)));
+    assert!(matches!(parse("燃料 = 水 1/s"), Error::UnknownChar(_, '/', 1, _)));
+    assert!(matches!(parse("燃料 = 水 1.5.5s"), Error::UnknownChar(_, '.', 1, _)));
+}
+
+#[test]
+fn test_time_unit_and_rate() {
+    let input = "铁锭 500ms\n铁板 = 铁锭 2m\n2*铁棒 = 铁锭 @ 30/min\n螺丝 = 铁棒 @0.5/s @构筑站\n钢锭 = 铁锭 @ 1800/h";
+    let output = FormulaList::from_reader(input.as_bytes()).unwrap();
+    let formulas = output.formulas();
+    assert_eq!(formulas[0].speed(), 120.into());
+    assert_eq!(formulas[1].speed(), Fraction::new(1, 2));
+    assert_eq!(formulas[2].speed(), 15.into());
+    assert_eq!(formulas[3].speed(), 30.into());
+    assert_eq!(formulas[3].machine().unwrap().to_string(), "构筑站");
+    assert_eq!(formulas[4].speed(), 30.into());
+
+    let parse = |input: &str| FormulaList::from_reader(input.as_bytes()).unwrap_err();
+    assert!(matches!(parse("铁板 = 铁锭 0ms"), Error::InvalidAmount(_, 1, _, None)));
+    assert!(matches!(parse("铁板 = 铁锭 @ 30"), Error::UnexpectedEof(_, 1, _)));
+    assert!(matches!(parse("铁板 = 铁锭 @ 30/day"), Error::UnknownChar(_, 'd', 1, _)));
+    assert!(matches!(parse("铁板 = 铁锭 @构筑站"), Error::UnknownChar(_, '构', 1, _)));
+}
+
+#[test]
+fn test_parse_recovery() {
+    let input = "铁矿石 1s\r\n铁锭 = 铁矿石 2x\r\n铁棒 = 铁锭 4s\n铁棒 = 铁锭 4s\n螺丝 = 0*铁棒 1s\n铁板 = 3*铁锭 6s\n钢锭 = 铁矿石";
+    let (list, diagnostics) = FormulaList::parse(input.as_bytes()).unwrap();
+    let formulas: Vec<String> = list.formulas().iter().map(|x| x.to_string()).collect();
+    assert_eq!(
+        formulas,
+        vec!["铁矿石*1 = 60/min", "铁棒*1 = 铁锭*1 15/min", "铁板*1 = 铁锭*3 10/min"]
+    );
+
+    // 铁锭 is consumed but its line has an error
+    let (warnings, diagnostics): (Vec<_>, Vec<_>) = diagnostics.into_iter().partition(|x| x.is_warning());
+    assert_eq!(warnings.len(), 1);
+    let v = &warnings[0];
+    assert!(matches!(&v.error, Error::UnproducedItem(item, 3) if item.to_string() == "铁锭"));
+    assert_eq!((v.line, v.span.clone()), (3, 5..7));
+    assert_eq!(v.to_string(), "Item 铁锭 at line 3 is neither produced nor supplied");
+
+    assert_eq!(diagnostics.len(), 4);
+    let v = &diagnostics[0];
+    assert!(matches!(v.error, Error::UnknownChar(_, 'x', 2, 11)));
+    assert_eq!((v.line, v.span.clone()), (2, 10..11));
+    assert_eq!(v.expected.as_deref(), Some("`ms`, `s` or `m` after the time"));
+    assert_eq!(v.source, "铁锭 = 铁矿石 2x");
+
+    let v = &diagnostics[1];
+    assert!(matches!(v.error, Error::DuplicateRecipe(_, 4)));
+    assert_eq!((v.line, v.span.clone(), v.expected.as_ref()), (4, 0..10, None));
+
+    let v = &diagnostics[2];
+    assert!(matches!(v.error, Error::InvalidAmount(_, 5, 6, None)));
+    assert_eq!(v.span, 5..6);
+
+    let v = &diagnostics[3];
+    assert!(matches!(v.error, Error::UnexpectedEof(_, 7, 9)));
+    assert_eq!(v.span, 8..8);
+    assert_eq!(v.to_string(), "Unexpected end of input at line 7, col 9, expected a time like `2s` or a rate like `@ 30/min`");
+
+    // the first error is still the error of from_reader
+    let e = FormulaList::from_reader(input.as_bytes()).unwrap_err();
+    assert!(matches!(e, Error::UnknownChar(_, 'x', 2, 11)));
+}
+
+#[test]
+fn test_parse_recovery_items() {
+    let input = "铁矿石 1s\n铁板 = 铁定 + 3s\n@冶炼炉 4MW\n钢锭 = 铁矿石 4x @构筑站\n铁锭 = 铁矿石 2s 冶炼炉\n~传送带 60 x\n";
+    let (list, diagnostics) = FormulaList::parse(input.as_bytes()).unwrap();
+    assert_eq!(diagnostics.len(), 4);
+    // a line with an error after a whole formula or transport adds neither
+    assert_eq!(list.formulas().len(), 1);
+    assert!(list.transports().is_empty());
+    // the names of the rejected lines are not items of the list
+    let items: Vec<String> = list.items().iter().map(|x| x.to_string()).collect();
+    assert_eq!(items, vec!["铁矿石"]);
+    let machines: Vec<String> = list.machines().iter().map(|x| x.to_string()).collect();
+    assert_eq!(machines, vec!["冶炼炉"]);
+}
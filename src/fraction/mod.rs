@@ -6,19 +6,69 @@ use std::{
     ops::{Add, AddAssign, Div, Mul},
 };
 
+use crate::{Error, Result};
+
 /// unsigned Fraction
+///
+/// Operations are done in `u128` and reduced before stored in `u64`, the `checked_*` methods
+/// return [`Error::Overflow`] if the result does not fit while the operators panic.
 #[derive(PartialEq, Eq, Clone, Copy)]
-pub struct Fraction(u32, u32);
+pub struct Fraction(u64, u64);
 
 impl Fraction {
-    pub fn new(amount: u32, unit: u32) -> Self {
+    pub fn new(amount: u64, unit: u64) -> Self {
         if unit == 0 {
             panic!("unit can not be 0");
         }
-        let g = gcd(amount, unit);
+        let g = gcd(amount.into(), unit.into()) as u64;
         Fraction(amount / g, unit / g)
     }
 
+    pub fn amount(&self) -> u64 {
+        self.0
+    }
+
+    pub fn unit(&self) -> u64 {
+        self.1
+    }
+
+    pub fn checked_add(self, rhs: Self) -> Result<Self> {
+        let unit = lcm(self.1.into(), rhs.1.into());
+        let l = u128::from(self.0).checked_mul(unit / u128::from(self.1));
+        let r = u128::from(rhs.0).checked_mul(unit / u128::from(rhs.1));
+        let amount = l
+            .zip(r)
+            .and_then(|(l, r)| l.checked_add(r))
+            .ok_or(Error::Overflow)?;
+        reduce(amount, unit)
+    }
+
+    /// `None` if the result would be negative.
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        let unit = lcm(self.1.into(), rhs.1.into());
+        let amount = (u128::from(self.0) * (unit / u128::from(self.1)))
+            .checked_sub(u128::from(rhs.0) * (unit / u128::from(rhs.1)))?;
+        Some(reduce(amount, unit).expect("difference is smaller than the operands"))
+    }
+
+    pub fn checked_mul(self, rhs: Self) -> Result<Self> {
+        reduce(
+            u128::from(self.0) * u128::from(rhs.0),
+            u128::from(self.1) * u128::from(rhs.1),
+        )
+    }
+
+    /// Dividing by zero is an overflow too.
+    pub fn checked_div(self, rhs: Self) -> Result<Self> {
+        if rhs.0 == 0 {
+            return Err(Error::Overflow);
+        }
+        reduce(
+            u128::from(self.0) * u128::from(rhs.1),
+            u128::from(self.1) * u128::from(rhs.0),
+        )
+    }
+
     pub fn cell(&self) -> Self {
         let m = self.0 % self.1;
         let m = if m > 0 { 1 } else { 0 };
@@ -30,9 +80,7 @@ impl Add for Fraction {
     type Output = Fraction;
 
     fn add(self, rhs: Self) -> Self::Output {
-        let unit = lcm(self.1, rhs.1);
-        let amount = self.0 * (unit / self.1) + rhs.0 * (unit / rhs.1);
-        Fraction::new(amount, unit)
+        self.checked_add(rhs).expect("fraction overflow")
     }
 }
 
@@ -46,7 +94,7 @@ impl Mul for Fraction {
     type Output = Fraction;
 
     fn mul(self, rhs: Self) -> Self::Output {
-        Fraction::new(self.0 * rhs.0, self.1 * rhs.1)
+        self.checked_mul(rhs).expect("fraction overflow")
     }
 }
 
@@ -54,13 +102,30 @@ impl Div for Fraction {
     type Output = Fraction;
 
     fn div(self, rhs: Self) -> Self::Output {
-        Fraction::new(self.0 * rhs.1, self.1 * rhs.0)
+        if rhs.0 == 0 {
+            panic!("divide by 0");
+        }
+        self.checked_div(rhs).expect("fraction overflow")
+    }
+}
+
+impl PartialOrd for Fraction {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Fraction {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        let l = u128::from(self.0) * u128::from(other.1);
+        let r = u128::from(other.0) * u128::from(self.1);
+        l.cmp(&r)
     }
 }
 
 impl From<u32> for Fraction {
     fn from(value: u32) -> Self {
-        Fraction(value, 1)
+        Fraction(value.into(), 1)
     }
 }
 
@@ -85,7 +150,42 @@ impl Display for Fraction {
     }
 }
 
-fn gcd(l: u32, r: u32) -> u32 {
+/// Exact `"n/d"` string, `"n"` is read as a whole number.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Fraction {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(&format_args!("{}/{}", self.0, self.1))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Fraction {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let v = String::deserialize(deserializer)?;
+        let (amount, unit) = v.split_once('/').unwrap_or((&v, "1"));
+        match (amount.parse::<u64>(), unit.parse::<u64>()) {
+            (Ok(amount), Ok(unit)) if unit != 0 => Ok(Fraction::new(amount, unit)),
+            _ => Err(D::Error::custom(format!("invalid fraction {v}"))),
+        }
+    }
+}
+
+/// Reduce `amount / unit` into a [`Fraction`].
+fn reduce(amount: u128, unit: u128) -> Result<Fraction> {
+    let g = gcd(amount, unit);
+    let amount = u64::try_from(amount / g).map_err(|_| Error::Overflow)?;
+    let unit = u64::try_from(unit / g).map_err(|_| Error::Overflow)?;
+    Ok(Fraction(amount, unit))
+}
+
+fn gcd(l: u128, r: u128) -> u128 {
     let (mut l, mut r) = (l, r);
     if l < r {
         (l, r) = (r, l);
@@ -99,7 +199,7 @@ fn gcd(l: u32, r: u32) -> u32 {
     }
 }
 
-fn lcm(l: u32, r: u32) -> u32 {
+fn lcm(l: u128, r: u128) -> u128 {
     if l == 0 && r == 0 {
         panic!("no lcm for 0 and 0")
     }
@@ -28,7 +28,50 @@ fn test_mul() {
     }
 }
 
+#[test]
+fn test_checked_sub() {
+    assert_eq!(Some(Fraction(1, 6)), Fraction(1, 2).checked_sub(Fraction(1, 3)));
+    assert_eq!(Some(Fraction(0, 1)), Fraction(2, 3).checked_sub(Fraction(2, 3)));
+    assert_eq!(None, Fraction(1, 3).checked_sub(Fraction(1, 2)));
+}
+
+#[test]
+fn test_cmp() {
+    assert!(Fraction(1, 3) < Fraction(1, 2));
+    assert!(Fraction(5, 4) > Fraction(1, 1));
+    assert_eq!(Fraction(2, 3).cmp(&Fraction(2, 3)), std::cmp::Ordering::Equal);
+}
+
 #[test]
 fn test_div() {
-    // TODO
+    struct Case(Fraction, Fraction, Fraction);
+    let cases = vec![
+        Case(Fraction(1, 2), Fraction(1, 3), Fraction(3, 2)),
+        Case(Fraction(2, 3), Fraction(4, 5), Fraction(5, 6)),
+        Case(Fraction(0, 1), Fraction(4, 15), Fraction(0, 1)),
+    ];
+
+    for Case(lhs, rhs, result) in cases {
+        assert_eq!(result, lhs / rhs)
+    }
+}
+
+#[test]
+fn test_overflow() {
+    // values beyond u32 are fine
+    let big = Fraction::new(u32::MAX.into(), 1);
+    assert_eq!(big * big, Fraction::new(u64::from(u32::MAX) * u64::from(u32::MAX), 1));
+    // reduced before stored
+    let v = Fraction::new(u64::MAX, 3);
+    assert_eq!(v.checked_mul(Fraction::new(3, u64::MAX)).unwrap(), 1.into());
+
+    let max = Fraction::new(u64::MAX, 1);
+    assert!(matches!(max.checked_add(1.into()), Err(crate::Error::Overflow)));
+    // the sum of the scaled amounts does not fit in u128
+    let l = Fraction::new(u64::MAX - 2, u64::MAX - 1);
+    let r = Fraction::new(u64::MAX - 4, u64::MAX);
+    assert!(matches!(l.checked_add(r), Err(crate::Error::Overflow)));
+    assert!(matches!(max.checked_mul(max), Err(crate::Error::Overflow)));
+    assert!(matches!(max.checked_div(0.into()), Err(crate::Error::Overflow)));
+    assert_eq!(max.checked_sub(max), Some(0.into()));
 }
\ No newline at end of file
@@ -0,0 +1,322 @@
+//! Production planning by walking the formulas from the target down to the raw items
+//!
+//! Each required item uses the formula chosen by the [`RecipeSelection`], and a formula is
+//! calculated once everything consuming its outputs is known. Formulas feeding each other
+//! form a strongly connected component, whose steady state is solved with the [`simplex`].
+//!
+//! [`simplex`]: crate::simplex
+
+#[cfg(test)]
+mod test;
+
+use std::{collections::HashMap, rc::Rc};
+
+use crate::{
+    linear::net_output,
+    simplex::{self, Constraint, Field, Relation},
+    Error, Formula, FormulaItem, FormulaList, Fraction, Item, Number, Plan, RecipeSelection,
+    Result, Trace,
+};
+
+pub(crate) fn calculate<N: Number>(
+    meta: &FormulaList,
+    source: &[Rc<Item>],
+    targets: &[(Rc<Item>, Fraction)],
+    selection: &RecipeSelection,
+    trace: Option<&dyn Trace>,
+) -> Result<Vec<Plan<N>>> {
+    let selected = selection.select(meta, source)?;
+
+    // a formula selected for several required items is calculated once
+    let mut data: Vec<Data> = Vec::new();
+    let mut data_of: HashMap<Rc<Item>, usize> = HashMap::new();
+    let mut required_item: Vec<Rc<Item>> = Vec::new();
+    required_item.extend(targets.iter().map(|(x, _)| x.clone()));
+    while let Some(target_i) = required_item.pop() {
+        if data_of.contains_key(&target_i) {
+            continue;
+        }
+        let (recipe, formula) = selected
+            .get(&target_i)
+            .ok_or_else(|| Error::MissingRecipe(target_i.clone()))?
+            .clone();
+        let is_source = source.contains(&target_i);
+        let index = data
+            .iter()
+            .position(|x| !is_source && !x.is_source && Rc::ptr_eq(&x.formula, &formula));
+        let index = match index {
+            Some(v) => v,
+            None => {
+                if !is_source {
+                    for input_i in formula.source() {
+                        required_item.push(input_i.item());
+                    }
+                }
+                data.push(Data {
+                    formula,
+                    recipe,
+                    is_source,
+                    items: Vec::new(),
+                    used: 0,
+                });
+                data.len() - 1
+            }
+        };
+        data[index].items.push(target_i.clone());
+        data_of.insert(target_i, index);
+    }
+
+    // a formula is calculated after all its consumers and all producers of its byproducts
+    let mut edges: Vec<Vec<usize>> = vec![Vec::new(); data.len()];
+    for (i, d) in data.iter().enumerate() {
+        if d.is_source {
+            continue;
+        }
+        for input_i in d.formula.source() {
+            edges[i].push(data_of[&input_i.item()]);
+        }
+        for output_i in d.formula.targets() {
+            match data_of.get(&output_i.item()) {
+                Some(&j) if j != i => edges[i].push(j),
+                _ => {}
+            }
+        }
+    }
+    let component = strongly_connected(&edges);
+    let mut members: Vec<Vec<usize>> = Vec::new();
+    for (i, &c) in component.iter().enumerate() {
+        if members.len() <= c {
+            members.resize(c + 1, Vec::new());
+        }
+        members[c].push(i);
+    }
+    for (i, e) in edges.iter().enumerate() {
+        for &j in e {
+            if component[i] != component[j] {
+                data[j].used += 1;
+            }
+        }
+    }
+
+    if let Some(trace) = trace {
+        for d in data.iter() {
+            for item in d.items.iter() {
+                trace.visit(item, &d.formula, d.used);
+            }
+        }
+    }
+
+    // production minus consumption of every item, the targets start at their shortfall
+    let mut balance: HashMap<Rc<Item>, N::Signed> = HashMap::new();
+    let mut producer: HashMap<Rc<Item>, usize> = HashMap::new();
+    for (target, amount) in targets {
+        add(&mut balance, target.clone(), -N::from_fraction(*amount).to_signed())?;
+    }
+    // targets not consumed by other formulas come first
+    let mut calculated: Vec<usize> = (0..members.len())
+        .rev()
+        .filter(|&c| members[c].iter().all(|&k| data[k].used == 0))
+        .collect();
+    let mut results: Vec<Plan<N>> = Vec::new();
+    while let Some(c) = calculated.pop() {
+        let crafts = match members[c][..] {
+            [index] if !edges[index].contains(&index) => {
+                vec![(index, single_crafts(&data[index], &balance)?)]
+            }
+            _ => loop_crafts(&data, &members[c], &balance)?,
+        };
+
+        for &(index, crafts) in crafts.iter() {
+            let d = &data[index];
+            let formula = d.formula.clone();
+            if let Some(trace) = trace {
+                trace.crafts(&formula, &crafts);
+            }
+            if d.is_source {
+                // only the supplied item is credited, the formula is not run
+                if crafts != N::zero() {
+                    let item = d.items[0].clone();
+                    let plan: Plan<N> = Plan::supplying(meta, formula, d.recipe, item.clone(), crafts)?;
+                    add(&mut balance, item.clone(), plan.speed.to_signed())?;
+                    results.push(plan);
+                    producer.insert(item, results.len() - 1);
+                }
+                continue;
+            }
+            if crafts != N::zero() {
+                results.push(Plan::new(meta, formula.clone(), d.recipe, crafts)?);
+                for output_i in formula.targets() {
+                    add(&mut balance, output_i.item(), flow(output_i, crafts)?)?;
+                    producer.insert(output_i.item(), results.len() - 1);
+                }
+            }
+            for input_i in formula.source() {
+                add(&mut balance, input_i.item(), -flow(input_i, crafts)?)?;
+            }
+        }
+
+        for &(index, _) in crafts.iter() {
+            for &j in edges[index].iter() {
+                if component[j] == c {
+                    continue;
+                }
+                data[j].used -= 1;
+                if data[j].used == 0 && members[component[j]].iter().all(|&k| data[k].used == 0) {
+                    calculated.push(component[j]);
+                }
+            }
+        }
+    }
+
+    for item in meta.items() {
+        let (Some(&p), Some(&v)) = (producer.get(item), balance.get(item)) else {
+            continue;
+        };
+        if v.is_positive() || v.is_negative() {
+            results[p].balance.push((item.clone(), v));
+        }
+    }
+
+    let results: Vec<_> = results.into_iter().rev().collect();
+    Ok(results)
+}
+
+#[derive(Debug)]
+struct Data {
+    formula: Rc<Formula>,
+    recipe: usize,
+    is_source: bool,
+    /// required items this formula is selected for
+    items: Vec<Rc<Item>>,
+    /// formulas outside its loop still to be calculated before it
+    used: u32,
+}
+
+/// `crafts` times the amount of `v`.
+fn flow<N: Number>(v: &FormulaItem, crafts: N) -> Result<N::Signed> {
+    let amount = crafts.checked_mul(N::from_fraction(v.amount()))?;
+    Ok(amount.to_signed())
+}
+
+fn add<F: Field>(balance: &mut HashMap<Rc<Item>, F>, item: Rc<Item>, v: F) -> Result<()> {
+    let total = balance.entry(item).or_insert(F::ZERO);
+    *total = total.checked_add(v)?;
+    Ok(())
+}
+
+/// Crafts per minute needed to cover what byproducts did not.
+fn single_crafts<N: Number>(d: &Data, balance: &HashMap<Rc<Item>, N::Signed>) -> Result<N> {
+    let mut crafts = N::zero();
+    for item in d.items.iter() {
+        let v = balance.get(item).copied().unwrap_or(N::Signed::ZERO);
+        let need = N::from_signed(-v)?;
+        let output = d.formula.output(item).unwrap().amount();
+        let v = need.checked_div(N::from_fraction(output))?;
+        if v > crafts {
+            crafts = v;
+        }
+    }
+    Ok(crafts)
+}
+
+/// Steady state crafts per minute of formulas feeding each other, with the fewest buildings.
+fn loop_crafts<N: Number>(
+    data: &[Data],
+    members: &[usize],
+    balance: &HashMap<Rc<Item>, N::Signed>,
+) -> Result<Vec<(usize, N)>> {
+    let items: Vec<Rc<Item>> = members
+        .iter()
+        .flat_map(|&k| data[k].items.iter().cloned())
+        .collect();
+    let mut constraints = Vec::new();
+    for item in items.iter() {
+        let v = balance.get(item).copied().unwrap_or(N::Signed::ZERO);
+        constraints.push(Constraint {
+            coefficients: members
+                .iter()
+                .map(|&k| net_output(&data[k].formula, item))
+                .collect::<Result<_>>()?,
+            relation: Relation::Ge,
+            value: -v,
+        });
+    }
+    let buildings: Vec<N::Signed> = members
+        .iter()
+        .map(|&k| N::Signed::ONE.checked_div(N::from_fraction(data[k].formula.speed()).to_signed()))
+        .collect::<Result<_>>()?;
+
+    let crafts = match simplex::minimize(&buildings, &constraints) {
+        Ok(v) => v,
+        Err(Error::Infeasible) => return Err(Error::Cycle(items)),
+        Err(e) => return Err(e),
+    };
+    members
+        .iter()
+        .zip(crafts)
+        .map(|(&k, v)| Ok((k, N::from_signed(v)?)))
+        .collect()
+}
+
+/// Component of every node with Tarjan's algorithm, numbered so that
+/// edges only go from a component to itself or to a later one.
+fn strongly_connected(edges: &[Vec<usize>]) -> Vec<usize> {
+    struct State<'a> {
+        edges: &'a [Vec<usize>],
+        index: Vec<Option<usize>>,
+        low: Vec<usize>,
+        stack: Vec<usize>,
+        on_stack: Vec<bool>,
+        next: usize,
+        component: Vec<usize>,
+        count: usize,
+    }
+
+    fn visit(s: &mut State, v: usize) {
+        s.index[v] = Some(s.next);
+        s.low[v] = s.next;
+        s.next += 1;
+        s.stack.push(v);
+        s.on_stack[v] = true;
+        for &w in s.edges[v].iter() {
+            match s.index[w] {
+                None => {
+                    visit(s, w);
+                    s.low[v] = s.low[v].min(s.low[w]);
+                }
+                Some(i) if s.on_stack[w] => s.low[v] = s.low[v].min(i),
+                _ => {}
+            }
+        }
+        if Some(s.low[v]) == s.index[v] {
+            loop {
+                let w = s.stack.pop().unwrap();
+                s.on_stack[w] = false;
+                s.component[w] = s.count;
+                if w == v {
+                    break;
+                }
+            }
+            s.count += 1;
+        }
+    }
+
+    let n = edges.len();
+    let mut s = State {
+        edges,
+        index: vec![None; n],
+        low: vec![0; n],
+        stack: Vec::new(),
+        on_stack: vec![false; n],
+        next: 0,
+        component: vec![0; n],
+        count: 0,
+    };
+    for v in 0..n {
+        if s.index[v].is_none() {
+            visit(&mut s, v);
+        }
+    }
+    // Tarjan finds the components in reverse topological order
+    s.component.iter().map(|c| s.count - 1 - c).collect()
+}
@@ -0,0 +1,88 @@
+use super::*;
+use crate::Fraction;
+
+#[test]
+fn test_strongly_connected() {
+    let edges = vec![vec![1], vec![2], vec![1, 3], vec![]];
+    assert_eq!(strongly_connected(&edges), vec![0, 1, 1, 2]);
+
+    let edges = vec![vec![0, 1], vec![]];
+    assert_eq!(strongly_connected(&edges), vec![0, 1]);
+}
+
+#[test]
+fn test_loop() {
+    let input = "燃料 1s
+2*塑料 = 橡胶 + 燃料 6s
+2*橡胶 = 塑料 + 燃料 6s
+产品 = 塑料 6s";
+    let formula_list = FormulaList::from_reader(input.as_bytes()).unwrap();
+    let target = formula_list.get_item("产品").unwrap();
+    let r = calculate::<Fraction>(&formula_list, &[], &[(target, 10.into())], &RecipeSelection::First, None).unwrap();
+
+    let speed = |name: &str| {
+        let item = formula_list.get_item(name).unwrap();
+        r.iter()
+            .find(|x| x.formula.target().item() == item)
+            .unwrap()
+            .speed
+    };
+    assert_eq!(speed("塑料"), Fraction::new(40, 3));
+    assert_eq!(speed("橡胶"), Fraction::new(20, 3));
+    assert_eq!(speed("燃料"), 10.into());
+    assert_eq!(
+        r.last().unwrap().formula.target().item().to_string(),
+        "产品"
+    );
+    assert!(r.iter().all(|x| x.balance.is_empty()));
+}
+
+#[test]
+fn test_self_loop() {
+    let input = "B 1s
+2*A = A + B 6s";
+    let formula_list = FormulaList::from_reader(input.as_bytes()).unwrap();
+    let target = formula_list.get_item("A").unwrap();
+    let r = calculate::<Fraction>(&formula_list, &[], &[(target, 10.into())], &RecipeSelection::First, None).unwrap();
+    assert_eq!(r.len(), 2);
+    assert_eq!(r[0].speed, 10.into());
+    assert_eq!(r[1].speed, 20.into());
+}
+
+#[test]
+fn test_unsolvable_loop() {
+    let input = "A = B 1s
+B = A 1s";
+    let formula_list = FormulaList::from_reader(input.as_bytes()).unwrap();
+    let target = formula_list.get_item("A").unwrap();
+    let r = calculate::<Fraction>(&formula_list, &[], &[(target, 10.into())], &RecipeSelection::First, None);
+    let Err(Error::Cycle(items)) = r else {
+        panic!("expect a cycle error");
+    };
+    assert_eq!(items.len(), 2);
+}
+
+#[test]
+fn test_byproduct_source() {
+    let input = "原油 1s
+2*塑料 + 重油残渣 = 3*原油 6s
+燃料 = 重油残渣 2s";
+    let formula_list = FormulaList::from_reader(input.as_bytes()).unwrap();
+    let target = formula_list.get_item("燃料").unwrap();
+    let residue = formula_list.get_item("重油残渣").unwrap();
+    let source = [residue.clone()];
+    let r = calculate::<Fraction>(
+        &formula_list,
+        &source,
+        &[(target, 30.into())],
+        &RecipeSelection::First,
+        None,
+    )
+    .unwrap();
+    assert_eq!(r.len(), 2);
+    // the plan of the source is credited with the supplied item only
+    assert_eq!(r[0].item, residue);
+    assert_eq!(r[0].speed, 30.into());
+    assert_eq!(r[0].crafts().unwrap(), 30.into());
+    assert!(r.iter().all(|x| x.balance.is_empty()));
+}
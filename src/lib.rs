@@ -4,102 +4,170 @@ mod test;
 pub mod formula;
 pub mod fraction;
 pub mod error;
+pub mod recipe;
+pub mod linear;
+pub mod number;
+pub mod logistics;
+pub mod rational;
+pub mod supply;
+pub mod summary;
+pub mod trace;
+pub mod report;
+#[cfg(feature = "docs")]
+pub mod docs;
+mod graph;
+mod simplex;
 
-use std::collections::HashMap;
 use std::rc::Rc;
 
-pub use error::{Error, Result};
-pub use formula::{Formula, FormulaItem, Item, FormulaList, Plan};
+pub use error::{Diagnostic, Error, Result};
+pub use formula::{Buildings, Formula, FormulaItem, Item, FormulaList, Machine, Plan, RateUnit, Transport};
+pub use logistics::Flow;
 pub use fraction::Fraction;
+pub use rational::Rational;
+pub use recipe::{RecipeSelection, Selected};
+pub use report::Report;
+pub use linear::Objective;
+pub use number::Number;
+pub use summary::PlanResult;
+pub use trace::Trace;
 
-pub fn calculate<'a>(
-    meta: &'a FormulaList,
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// how the recipe of an item is chosen, the linear solver only uses it for source items
+    pub selection: RecipeSelection,
+    pub solver: Solver,
+    /// maximum total power in MW, the linear solver picks recipes to stay below it
+    pub power_limit: Option<u32>,
+    /// run the buildings of a plan at the same clock, up to this percent,
+    /// instead of full speed with the last one underused
+    pub overclock: Option<u32>,
+    /// steps of the graph solver, e.g. [`trace::Stderr`]
+    pub trace: Option<Rc<dyn Trace>>,
+    /// unit of the rates the plans display
+    pub rate_unit: RateUnit,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Solver {
+    /// walk the formulas from the target down to the raw items, one recipe per item
+    #[default]
+    Graph,
+    /// solve all formulas as a linear program, choosing recipes by the objective
+    Linear(Objective),
+}
+
+pub fn calculate(
+    meta: &FormulaList,
     source: &[Rc<Item>],
     target: Rc<Item>,
     amount: u32,
-) -> Vec<Plan> {
-    let formulas = meta.formulas();
-    let mut data: HashMap<_, _> = formulas
-        .iter()
-        .map(|x| {
-            (
-                x.target().item(),
-                Data {
-                    formula: x.clone(),
-                    is_source: false,
-                    used: 0,
-                    speed: 0.into(),
-                },
-            )
-        })
-        .collect();
-    for v in source {
-        data.get_mut(v)
-            .unwrap_or_else(|| todo!("unknown item"))
-            .is_source = true;
-    }
-
-    let mut required_item: Vec<Rc<Item>> = Vec::new();
-    required_item.push(target.clone());
-    while let Some(target_i) = required_item.pop() {
-        let d = data.get(&target_i).unwrap_or_else(|| todo!("unknown item"));
-        if d.is_source {
-            continue;
-        }
-        let formula = d.formula.clone();
+    options: &Options,
+) -> Result<Vec<Plan>> {
+    calculate_as(meta, source, target, amount, options)
+}
 
-        for input_i in formula.source() {
-            let input_d = data.get_mut(&input_i.item()).unwrap();
-            input_d.used += 1;
-            if input_d.used == 1 {
-                required_item.push(input_i.item())
-            }
-        }
-    }
+/// [`calculate`] with another [`Number`], e.g. `f64`.
+pub fn calculate_as<N: Number>(
+    meta: &FormulaList,
+    source: &[Rc<Item>],
+    target: Rc<Item>,
+    amount: u32,
+    options: &Options,
+) -> Result<Vec<Plan<N>>> {
+    calculate_many_as(meta, source, &[(target, amount)], options)
+}
 
-    dbg!(&data);
+/// Plans producing all `targets` at their rates at once, formulas of shared intermediate
+/// items are merged into one plan.
+pub fn calculate_many(
+    meta: &FormulaList,
+    source: &[Rc<Item>],
+    targets: &[(Rc<Item>, u32)],
+    options: &Options,
+) -> Result<Vec<Plan>> {
+    calculate_many_as(meta, source, targets, options)
+}
 
-    data.get_mut(&target)
-        .unwrap_or_else(|| todo!("missing target "))
-        .speed = amount.into();
-    let mut calculated_item: Vec<Rc<Item>> = Vec::new();
-    calculated_item.push(target);
-    let mut results: Vec<Plan> = Vec::new();
-    while let Some(item) = calculated_item.pop() {
-        let d = data.get(&item).unwrap();
-        let speed: Fraction = d.speed.into();
-        let speed_f = d.formula.speed();
-        let speed_t = speed_f * d.formula.target().amount().into();
-        let group = speed / speed_t;
+/// [`calculate_many`] with another [`Number`], e.g. `f64`.
+pub fn calculate_many_as<N: Number>(
+    meta: &FormulaList,
+    source: &[Rc<Item>],
+    targets: &[(Rc<Item>, u32)],
+    options: &Options,
+) -> Result<Vec<Plan<N>>> {
+    let targets: Vec<(Rc<Item>, Fraction)> = targets
+        .iter()
+        .map(|(x, amount)| (x.clone(), (*amount).into()))
+        .collect();
+    solve(meta, source, &targets, options)
+}
 
-        results.push(Plan {
-            formula: d.formula.clone(),
-            speed,
-            group: group.cell(),
-            rate: speed / (group.cell() * speed_t),
-        });
-        if d.is_source {
-            continue;
-        }
-        let formula = d.formula.clone();
-        for v in formula.source() {
-            let sd = data.get_mut(&v.item()).unwrap();
-            sd.used -= 1;
-            sd.speed += group * speed_f * v.amount().into();
-            if sd.used == 0 {
-                calculated_item.push(v.item())
-            }
+fn solve<N: Number>(
+    meta: &FormulaList,
+    source: &[Rc<Item>],
+    targets: &[(Rc<Item>, Fraction)],
+    options: &Options,
+) -> Result<Vec<Plan<N>>> {
+    let mut plans = match options.solver {
+        Solver::Graph => graph::calculate(meta, source, targets, &options.selection, options.trace.as_deref())?,
+        Solver::Linear(objective) => linear::calculate(
+            meta,
+            source,
+            targets,
+            &options.selection,
+            objective,
+            options.power_limit,
+        )?,
+    };
+    if let Some(max) = options.overclock {
+        plans = plans
+            .into_iter()
+            .map(|x| x.overclock(meta, max))
+            .collect::<Result<_>>()?;
+    }
+    supply::check(meta, &plans)?;
+    // the linear solver only limits the power at 100% clock
+    if let Some(limit) = options.power_limit {
+        let power = Buildings::new(&plans)?.power;
+        if power > N::from_fraction(limit.into()) {
+            return Err(Error::PowerLimit(limit));
         }
     }
+    for plan in plans.iter_mut() {
+        plan.unit = options.rate_unit;
+    }
+    Ok(plans)
+}
 
-    let results: Vec<_> = results.into_iter().rev().collect();
-    results
+/// Most items of `target` per minute the supply of raw items and the power limit allow,
+/// [`Error::Unbounded`] if nothing limits it. Overclocking is not considered.
+pub fn max_rate(
+    meta: &FormulaList,
+    source: &[Rc<Item>],
+    target: Rc<Item>,
+    options: &Options,
+) -> Result<Fraction> {
+    supply::max_rate(meta, source, target, options)
 }
 
-#[derive(Debug)]
-struct Data {
-    formula: Rc<Formula>,
-    is_source: bool,
-    used: u32,
-    speed: Fraction,
+/// The most of `target` per minute `supply` items per minute can make, and its plans.
+/// The supplied items are sources, limited to their rate in addition to the supply
+/// declared in `meta`.
+pub fn maximize(
+    meta: &FormulaList,
+    supply: &[(Rc<Item>, u32)],
+    target: Rc<Item>,
+    options: &Options,
+) -> Result<(Fraction, Vec<Plan>)> {
+    let mut meta = meta.clone();
+    for (item, v) in supply {
+        let v = Fraction::from(*v);
+        let limit = meta.supply(item).map_or(v, |x| x.min(v));
+        meta.set_supply(item.clone(), limit);
+    }
+    let source: Vec<Rc<Item>> = supply.iter().map(|(x, _)| x.clone()).collect();
+    let rate = max_rate(&meta, &source, target.clone(), options)?;
+    let plans = solve(&meta, &source, &[(target, rate)], options)?;
+    Ok((rate, plans))
 }
@@ -0,0 +1,345 @@
+//! Production planning as a linear program
+//!
+//! Every formula gets a variable for its crafts per minute and every source item a variable
+//! for its supply per minute. For each item the production minus the consumption must cover
+//! the demand, so byproducts, alternate recipes and loops are all handled by the [`simplex`].
+//!
+//! [`simplex`]: crate::simplex
+
+#[cfg(test)]
+mod test;
+
+use std::{collections::HashMap, rc::Rc};
+
+use crate::{
+    recipe::Selected,
+    simplex::{self, Constraint, Field, Relation},
+    Error, Formula, FormulaList, Fraction, Item, Number, Plan, Rational, RecipeSelection,
+    Result,
+};
+
+/// What the linear solver minimizes, ties are broken by the other one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Objective {
+    /// raw items per minute, which are outputs of formulas without input and source items
+    #[default]
+    RawResource,
+    /// buildings, counted fractionally
+    Buildings,
+}
+
+/// The program is always solved exactly, only the plans are converted into `N`.
+pub(crate) fn calculate<N: Number>(
+    meta: &FormulaList,
+    source: &[Rc<Item>],
+    targets: &[(Rc<Item>, Fraction)],
+    selection: &RecipeSelection,
+    objective: Objective,
+    power_limit: Option<u32>,
+) -> Result<Vec<Plan<N>>> {
+    let formulas = formulas(meta, source);
+    let n = formulas.len() + source.len();
+    let mut constraints = balance(meta, &formulas, source, targets)?;
+
+    let mut raw = vec![Rational::ONE; n];
+    let mut buildings = vec![Rational::ZERO; n];
+    for (j, f) in formulas.iter().enumerate() {
+        raw[j] = if f.source().is_empty() {
+            f.targets()
+                .iter()
+                .try_fold(Rational::ZERO, |v, x| v.checked_add(x.amount().into()))?
+        } else {
+            Rational::ZERO
+        };
+        buildings[j] = Rational::ONE.checked_div(f.speed().into())?;
+    }
+    let (primary, secondary) = match objective {
+        Objective::RawResource => (raw, buildings),
+        Objective::Buildings => (buildings, raw),
+    };
+
+    let items = constraints.len();
+    constraints.extend(limits(meta, &formulas, source, power_limit)?);
+
+    let x = match simplex::minimize(&primary, &constraints) {
+        Ok(v) => v,
+        Err(Error::Infeasible) if constraints.len() > items => {
+            let (balance, limits) = constraints.split_at(items);
+            return Err(blame(meta, &primary, balance, limits, power_limit));
+        }
+        Err(e) => return Err(e),
+    };
+    let best = dot(&primary, &x)?;
+    constraints.push(Constraint {
+        coefficients: primary,
+        relation: Relation::Le,
+        value: best,
+    });
+    let x_r = simplex::minimize(&secondary, &constraints)?;
+    let x: Vec<N> = x_r.iter().map(|v| to_number(*v)).collect::<Result<_>>()?;
+
+    let mut order = Order {
+        meta,
+        formulas: &formulas,
+        source,
+        selected: selection.select(meta, source)?,
+        x: &x,
+        visited: vec![false; n],
+        results: Vec::new(),
+        producer: HashMap::new(),
+    };
+    for (target, _) in targets {
+        order.visit(target)?;
+    }
+    for item in meta.items() {
+        order.visit(item)?;
+    }
+    let Order {
+        mut results,
+        producer,
+        ..
+    } = order;
+
+    for (item, c) in meta.items().iter().zip(constraints.iter()) {
+        let balance = dot(&c.coefficients, &x_r)?.checked_sub(c.value)?;
+        if balance == Rational::ZERO {
+            continue;
+        }
+        if let Some(&p) = producer.get(item) {
+            let v = to_number::<N>(balance.abs())?.to_signed();
+            let v = if balance < Rational::ZERO { -v } else { v };
+            results[p].balance.push((item.clone(), v));
+        }
+    }
+
+    Ok(results)
+}
+
+/// Maximum rate of `target` within the supply and power limits.
+pub(crate) fn max_rate(
+    meta: &FormulaList,
+    source: &[Rc<Item>],
+    target: Rc<Item>,
+    power_limit: Option<u32>,
+) -> Result<Rational> {
+    let formulas = formulas(meta, source);
+    let n = formulas.len() + source.len();
+
+    // the rate is one more variable, taken from the balance of the target
+    let mut constraints = balance(meta, &formulas, source, &[])?;
+    for (item, c) in meta.items().iter().zip(constraints.iter_mut()) {
+        let v = if *item == target {
+            -Rational::ONE
+        } else {
+            Rational::ZERO
+        };
+        c.coefficients.push(v);
+    }
+    for mut c in limits(meta, &formulas, source, power_limit)? {
+        c.coefficients.push(Rational::ZERO);
+        constraints.push(c);
+    }
+    let mut objective = vec![Rational::ZERO; n];
+    objective.push(-Rational::ONE);
+
+    let x = simplex::minimize(&objective, &constraints)?;
+    Ok(x[n])
+}
+
+/// Formulas of source items are not run, the items are supplied instead.
+fn formulas(meta: &FormulaList, source: &[Rc<Item>]) -> Vec<Rc<Formula>> {
+    meta.formulas()
+        .iter()
+        .filter(|x| !source.contains(&x.target().item()))
+        .cloned()
+        .collect()
+}
+
+/// For every item, production minus consumption covers the demand of the targets.
+fn balance(
+    meta: &FormulaList,
+    formulas: &[Rc<Formula>],
+    source: &[Rc<Item>],
+    targets: &[(Rc<Item>, Fraction)],
+) -> Result<Vec<Constraint<Rational>>> {
+    let mut constraints = Vec::new();
+    for item in meta.items() {
+        let mut coefficients: Vec<Rational> = formulas
+            .iter()
+            .map(|x| net_output(x, item))
+            .collect::<Result<_>>()?;
+        coefficients.extend(source.iter().map(|x| supplied(x, item)));
+        let value = targets
+            .iter()
+            .filter(|(x, _)| x == item)
+            .try_fold(Rational::ZERO, |v, (_, amount)| v.checked_add((*amount).into()))?;
+        constraints.push(Constraint {
+            coefficients,
+            relation: Relation::Ge,
+            value,
+        });
+    }
+    Ok(constraints)
+}
+
+/// Production of every item with a limited supply, then the power if limited.
+fn limits(
+    meta: &FormulaList,
+    formulas: &[Rc<Formula>],
+    source: &[Rc<Item>],
+    power_limit: Option<u32>,
+) -> Result<Vec<Constraint<Rational>>> {
+    let mut constraints = Vec::new();
+    for item in meta.limited() {
+        let mut coefficients: Vec<Rational> = formulas
+            .iter()
+            .map(|f| match f.output(&item) {
+                Some(x) => x.amount().into(),
+                None => Rational::ZERO,
+            })
+            .collect();
+        coefficients.extend(source.iter().map(|x| supplied(x, &item)));
+        constraints.push(Constraint {
+            coefficients,
+            relation: Relation::Le,
+            value: meta.supply(&item).unwrap().into(),
+        });
+    }
+
+    if let Some(limit) = power_limit {
+        let mut coefficients: Vec<Rational> = formulas
+            .iter()
+            .map(|f| match f.machine().and_then(|x| meta.power(&x)) {
+                Some(v) => Rational::from(v).checked_div(f.speed().into()),
+                None => Ok(Rational::ZERO),
+            })
+            .collect::<Result<_>>()?;
+        coefficients.resize(formulas.len() + source.len(), Rational::ZERO);
+        constraints.push(Constraint {
+            coefficients,
+            relation: Relation::Le,
+            value: limit.into(),
+        });
+    }
+    Ok(constraints)
+}
+
+fn supplied(source: &Item, item: &Item) -> Rational {
+    if *source == *item {
+        Rational::ONE
+    } else {
+        Rational::ZERO
+    }
+}
+
+/// Why the `limits` make the program infeasible, blamed on the limits
+/// the solution without them exceeds.
+fn blame(
+    meta: &FormulaList,
+    objective: &[Rational],
+    balance: &[Constraint<Rational>],
+    limits: &[Constraint<Rational>],
+    power_limit: Option<u32>,
+) -> Error {
+    let x = match simplex::minimize(objective, balance) {
+        Ok(v) => v,
+        Err(e) => return e,
+    };
+    let mut items: Vec<Rc<Item>> = Vec::new();
+    for (item, c) in meta.limited().into_iter().zip(limits.iter()) {
+        match dot(&c.coefficients, &x) {
+            Ok(v) if v > c.value => items.push(item),
+            Ok(_) => {}
+            Err(e) => return e,
+        }
+    }
+    if !items.is_empty() {
+        return Error::SupplyLimit(items);
+    }
+    match power_limit {
+        Some(limit) => Error::PowerLimit(limit),
+        None => Error::Infeasible,
+    }
+}
+
+/// Output minus input of `item` per craft.
+pub(crate) fn net_output<F: Field>(formula: &Formula, item: &Item) -> Result<F> {
+    let output = formula
+        .targets()
+        .iter()
+        .filter(|x| *x.item() == *item)
+        .try_fold(F::ZERO, |v, x| v.checked_add(F::from_unsigned(x.amount())))?;
+    formula
+        .source()
+        .iter()
+        .filter(|x| *x.item() == *item)
+        .try_fold(output, |v, x| v.checked_sub(F::from_unsigned(x.amount())))
+}
+
+fn to_number<N: Number>(v: Rational) -> Result<N> {
+    let v = v.to_fraction().ok_or(Error::Overflow)?;
+    Ok(N::from_fraction(v))
+}
+
+fn dot(l: &[Rational], r: &[Rational]) -> Result<Rational> {
+    l.iter()
+        .zip(r.iter())
+        .try_fold(Rational::ZERO, |v, (l, r)| v.checked_add(l.checked_mul(*r)?))
+}
+
+/// Orders the plans so that producers come before their consumers.
+struct Order<'a, N: Number> {
+    meta: &'a FormulaList,
+    formulas: &'a [Rc<Formula>],
+    source: &'a [Rc<Item>],
+    selected: Selected,
+    x: &'a [N],
+    visited: Vec<bool>,
+    results: Vec<Plan<N>>,
+    /// index of the last plan producing an item
+    producer: HashMap<Rc<Item>, usize>,
+}
+
+impl<N: Number> Order<'_, N> {
+    fn visit(&mut self, item: &Rc<Item>) -> Result<()> {
+        if let Some(k) = self.source.iter().position(|x| x == item) {
+            let j = self.formulas.len() + k;
+            if self.visited[j] || self.x[j] == N::zero() {
+                return Ok(());
+            }
+            self.visited[j] = true;
+            let Some((recipe, formula)) = self.selected.get(item).cloned() else {
+                return Ok(());
+            };
+            let output = formula.output(item).unwrap().amount();
+            let crafts = self.x[j].checked_div(N::from_fraction(output))?;
+            self.producer.insert(item.clone(), self.results.len());
+            let plan = Plan::supplying(self.meta, formula, recipe, item.clone(), crafts)?;
+            self.results.push(plan);
+            return Ok(());
+        }
+
+        for j in 0..self.formulas.len() {
+            let formula = self.formulas[j].clone();
+            if self.visited[j] || self.x[j] == N::zero() || formula.output(item).is_none() {
+                continue;
+            }
+            self.visited[j] = true;
+            for input_i in formula.source() {
+                self.visit(&input_i.item())?;
+            }
+            let main = formula.target().item();
+            let recipe = self
+                .meta
+                .recipes(&main)
+                .iter()
+                .position(|x| Rc::ptr_eq(x, &formula))
+                .unwrap();
+            for output_i in formula.targets() {
+                self.producer.insert(output_i.item(), self.results.len());
+            }
+            self.results.push(Plan::new(self.meta, formula, recipe, self.x[j])?);
+        }
+        Ok(())
+    }
+}
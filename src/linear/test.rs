@@ -0,0 +1,143 @@
+use super::*;
+use crate::Fraction;
+
+fn speeds(plans: &[Plan]) -> Vec<(String, Fraction)> {
+    let mut v: Vec<_> = plans
+        .iter()
+        .map(|x| (x.formula.to_string(), x.speed))
+        .collect();
+    v.sort_by(|l, r| l.0.cmp(&r.0));
+    v
+}
+
+#[test]
+fn test_same_as_graph() {
+    let input = "铁矿石 1s
+铁锭 = 铁矿石 2s
+2*铁板 = 3*铁锭 6s
+铁棒 = 铁锭 4s
+4*螺丝 = 铁棒 6s
+加强铁板 = 6*铁板 + 12*螺丝 12s
+2*模块化框架 = 3*加强铁板 + 12*铁棒 60s";
+    let formula_list = FormulaList::from_reader(input.as_bytes()).unwrap();
+    let source = vec![formula_list.get_item("铁锭").unwrap()];
+    let target = formula_list.get_item("模块化框架").unwrap();
+
+    let graph = crate::calculate(
+        &formula_list,
+        &source,
+        target.clone(),
+        4,
+        &Default::default(),
+    )
+    .unwrap();
+    for objective in [Objective::RawResource, Objective::Buildings] {
+        let r = calculate::<Fraction>(
+            &formula_list,
+            &source,
+            &[(target.clone(), 4.into())],
+            &RecipeSelection::First,
+            objective,
+            None,
+        )
+        .unwrap();
+        assert_eq!(speeds(&graph), speeds(&r));
+        assert_eq!(r.last().unwrap().formula.target().item(), target);
+    }
+}
+
+#[test]
+fn test_alternate_and_byproduct() {
+    let input = "原油 1s
+水 1s
+2*塑料 + 重油残渣 = 3*原油 6s
+2*重油残渣 = 原油 6s
+4*塑料 = 原油 + 2*水 6s
+产品 = 塑料 + 重油残渣 6s";
+    let formula_list = FormulaList::from_reader(input.as_bytes()).unwrap();
+    let target = formula_list.get_item("产品").unwrap();
+    let oil = formula_list.get_item("原油").unwrap();
+
+    // 10 residue from 5 oil, 10 plastic from 5/2 oil and 5 water
+    let r = calculate::<Fraction>(
+        &formula_list,
+        &[],
+        &[(target.clone(), 10.into())],
+        &RecipeSelection::First,
+        Objective::RawResource,
+        None,
+    )
+    .unwrap();
+    let plan = r.iter().find(|x| x.formula.target().item() == oil).unwrap();
+    assert_eq!(plan.speed, Fraction::new(15, 2));
+    assert!(r.iter().all(|x| x.balance.is_empty()));
+
+    // the residue is left over
+    let input = "原油 1s
+2*塑料 + 重油残渣 = 3*原油 6s";
+    let formula_list = FormulaList::from_reader(input.as_bytes()).unwrap();
+    let plastic = formula_list.get_item("塑料").unwrap();
+    let r = calculate::<Fraction>(
+        &formula_list,
+        &[],
+        &[(plastic, 10.into())],
+        &RecipeSelection::First,
+        Objective::Buildings,
+        None,
+    )
+    .unwrap();
+    assert_eq!(r.len(), 2);
+    assert_eq!(
+        r[1].balance,
+        vec![(formula_list.get_item("重油残渣").unwrap(), 5.into())]
+    );
+}
+
+#[test]
+fn test_infeasible() {
+    let input = "20*生物质 = 4*木头 4s";
+    let formula_list = FormulaList::from_reader(input.as_bytes()).unwrap();
+    let target = formula_list.get_item("生物质").unwrap();
+    let r = calculate::<Fraction>(
+        &formula_list,
+        &[],
+        &[(target, 10.into())],
+        &RecipeSelection::First,
+        Objective::RawResource,
+        None,
+    );
+    assert!(matches!(r, Err(crate::Error::Infeasible)));
+}
+
+#[test]
+fn test_power_limit() {
+    let input = "原油 1s
+塑料 = 原油 6s @炼油厂
+塑料 = 2*原油 6s @精炼厂
+@炼油厂 30MW
+@精炼厂 5MW";
+    let formula_list = FormulaList::from_reader(input.as_bytes()).unwrap();
+    let target = formula_list.get_item("塑料").unwrap();
+    let run = |limit| {
+        calculate::<Fraction>(
+            &formula_list,
+            &[],
+            &[(target.clone(), 10.into())],
+            &RecipeSelection::First,
+            Objective::RawResource,
+            limit,
+        )
+    };
+
+    let r = run(None).unwrap();
+    assert_eq!(r.len(), 2);
+    assert_eq!(r[1].power, 30.into());
+
+    // 6 crafts at 18MW and 4 crafts at 2MW
+    let r = run(Some(20)).unwrap();
+    let power: Vec<_> = r.iter().map(|x| (x.speed, x.power)).collect();
+    assert!(power.contains(&(6.into(), Fraction::new(18, 1))));
+    assert!(power.contains(&(4.into(), 2.into())));
+
+    assert!(matches!(run(Some(4)), Err(crate::Error::PowerLimit(4))));
+}
@@ -0,0 +1,122 @@
+//! Moving items between the plans
+//!
+//! Every input of a plan is a [`Flow`] from the plan producing the item. The [`Transport`]
+//! carrying the item takes the lowest tier one line is enough for, or as many lines of the
+//! highest tier as needed.
+
+#[cfg(test)]
+mod test;
+
+use std::{fmt::Display, rc::Rc};
+
+use crate::{FormulaList, Fraction, Item, Number, Plan, RateUnit, Result, Transport};
+
+#[derive(Debug)]
+pub struct Flow<N: Number = Fraction> {
+    pub item: Rc<Item>,
+    /// index of the plan producing the item, `None` if no plan does
+    pub from: Option<usize>,
+    /// index of the plan consuming the item
+    pub to: usize,
+    /// items per minute
+    pub rate: N,
+    /// lines needed, `None` if no transport carries the item
+    pub lines: Option<Lines<N>>,
+    /// unit of the rate displayed, the same as the consuming plan
+    pub unit: RateUnit,
+}
+
+#[derive(Debug)]
+pub struct Lines<N: Number = Fraction> {
+    pub transport: Rc<Transport>,
+    /// index in [`Transport::tiers`]
+    pub tier: usize,
+    pub count: N,
+}
+
+impl<N: Number> Flow<N> {
+    /// The flow needs more than one line of the highest tier.
+    pub fn exceeds(&self) -> bool {
+        self.lines
+            .as_ref()
+            .is_some_and(|x| x.count > N::from_fraction(1.into()))
+    }
+}
+
+impl<N: Number> Display for Flow<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Flow {
+            item, rate, unit, ..
+        } = self;
+        let rate = rate
+            .checked_mul(N::from_fraction(unit.minutes()))
+            .map_err(|_| std::fmt::Error)?;
+        write!(f, "{item} {rate:.2}/{unit}")?;
+        let Some(lines) = &self.lines else {
+            return Ok(());
+        };
+        let name = lines.transport.tier_name(lines.tier);
+        write!(f, " ==> {} x {name}", lines.count)?;
+        if self.exceeds() {
+            write!(f, " (exceeds one line)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Flows of every input of `plans`, the plans of `source` items have no inputs.
+pub fn flows<N: Number>(
+    meta: &FormulaList,
+    source: &[Rc<Item>],
+    plans: &[Plan<N>],
+) -> Result<Vec<Flow<N>>> {
+    let mut flows = Vec::new();
+    for (to, plan) in plans.iter().enumerate() {
+        let formula = &plan.formula;
+        if source.contains(&plan.item) {
+            continue;
+        }
+        let crafts = plan.crafts()?;
+        for input_i in formula.source() {
+            let item = input_i.item();
+            let rate = crafts.checked_mul(N::from_fraction(input_i.amount()))?;
+            // the plan with the item as main output, otherwise as byproduct
+            let from = plans
+                .iter()
+                .position(|x| x.formula.target().item() == item)
+                .or_else(|| plans.iter().position(|x| x.formula.output(&item).is_some()));
+            let lines = match meta.transport(&item) {
+                Some(transport) => Some(lines(transport, rate)?),
+                None => None,
+            };
+            flows.push(Flow {
+                item,
+                from,
+                to,
+                rate,
+                lines,
+                unit: plan.unit,
+            });
+        }
+    }
+    Ok(flows)
+}
+
+fn lines<N: Number>(transport: Rc<Transport>, rate: N) -> Result<Lines<N>> {
+    let tiers = transport.tiers();
+    let fit = tiers
+        .iter()
+        .position(|x| N::from_fraction((*x).into()) >= rate);
+    let (tier, count) = match fit {
+        Some(tier) => (tier, N::from_fraction(1.into())),
+        None => {
+            let max = N::from_fraction(tiers[tiers.len() - 1].into());
+            (tiers.len() - 1, rate.checked_div(max)?.ceil())
+        }
+    };
+    Ok(Lines {
+        transport,
+        tier,
+        count,
+    })
+}
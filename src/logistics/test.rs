@@ -0,0 +1,54 @@
+use super::*;
+use crate::{calculate, Options};
+
+#[test]
+fn test_flows() {
+    let input = "铁矿石 1s
+水 1s
+铁锭 = 铁矿石 + 水 1s
+铁棒 = 铁锭 1s
+~传送带 60 120 270
+~管道 300: 水";
+    let formula_list = FormulaList::from_reader(input.as_bytes()).unwrap();
+    let target = formula_list.get_item("铁棒").unwrap();
+    let plans = calculate(&formula_list, &[], target, 100, &Options::default()).unwrap();
+    let flows = flows(&formula_list, &[], &plans).unwrap();
+    assert_eq!(flows.len(), 3);
+
+    let flow = |name: &str| {
+        let item = formula_list.get_item(name).unwrap();
+        flows.iter().find(|x| x.item == item).unwrap()
+    };
+    let ingot = flow("铁锭");
+    assert_eq!(plans[ingot.from.unwrap()].formula.target().item(), ingot.item);
+    assert_eq!(plans[ingot.to].formula.target().item().to_string(), "铁棒");
+    assert_eq!(ingot.to_string(), "铁锭 100.00/min ==> 1 x 传送带Mk2");
+    assert!(!ingot.exceeds());
+
+    let water = flow("水");
+    assert_eq!(water.lines.as_ref().unwrap().transport.to_string(), "管道");
+    assert!(!water.exceeds());
+
+    let plans = calculate(
+        &formula_list,
+        &[],
+        formula_list.get_item("铁棒").unwrap(),
+        600,
+        &Options::default(),
+    )
+    .unwrap();
+    let flows = super::flows(&formula_list, &[], &plans).unwrap();
+    let ore = flows.iter().find(|x| x.item.to_string() == "铁矿石").unwrap();
+    assert!(ore.exceeds());
+    assert_eq!(ore.to_string(), "铁矿石 600.00/min ==> 3 x 传送带Mk3 (exceeds one line)");
+
+    let options = Options {
+        rate_unit: RateUnit::Second,
+        ..Options::default()
+    };
+    let target = formula_list.get_item("铁棒").unwrap();
+    let plans = calculate(&formula_list, &[], target, 600, &options).unwrap();
+    let flows = super::flows(&formula_list, &[], &plans).unwrap();
+    let ore = flows.iter().find(|x| x.item.to_string() == "铁矿石").unwrap();
+    assert_eq!(ore.to_string(), "铁矿石 10.00/s ==> 3 x 传送带Mk3 (exceeds one line)");
+}
@@ -1,3 +1,21 @@
-fn main() {
-    println!("Hello, world!");
+mod cli;
+
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let command = match cli::Command::parse(std::env::args().skip(1)) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("error: {e}");
+            eprintln!("{}", cli::USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+    match command.run(&mut std::io::stdout(), &mut std::io::stderr()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
 }
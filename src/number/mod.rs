@@ -0,0 +1,143 @@
+//! Numbers a plan can be calculated with
+//!
+//! [`Fraction`] keeps every rate exact, `f64` is faster and never overflows on deep
+//! production chains but shows rounding errors like `13.333333333333334`.
+
+#[cfg(test)]
+mod test;
+
+use std::fmt::{Debug, Display};
+
+use crate::{
+    simplex::{Field, EPSILON},
+    Error, Fraction, Rational, Result,
+};
+
+/// Non-negative number used by [`crate::calculate_as`].
+pub trait Number: Copy + PartialEq + PartialOrd + Debug + Display {
+    /// signed number used to solve loops
+    type Signed: Field;
+
+    fn zero() -> Self;
+    fn from_fraction(v: Fraction) -> Self;
+    fn checked_add(self, rhs: Self) -> Result<Self>;
+    fn checked_mul(self, rhs: Self) -> Result<Self>;
+    fn checked_div(self, rhs: Self) -> Result<Self>;
+    /// `self - rhs`, or zero if it would be negative
+    fn saturating_sub(self, rhs: Self) -> Self;
+    /// smallest integer not less than self
+    fn ceil(self) -> Self;
+    /// `self` raised to `exponent`, rounded to millionths for [`Fraction`]
+    fn powf(self, exponent: f64) -> Result<Self>;
+    fn to_signed(self) -> Self::Signed;
+    /// negative values, which can only come from rounding, become zero
+    fn from_signed(v: Self::Signed) -> Result<Self>;
+}
+
+impl Number for Fraction {
+    type Signed = Rational;
+
+    fn zero() -> Self {
+        0.into()
+    }
+
+    fn from_fraction(v: Fraction) -> Self {
+        v
+    }
+
+    fn checked_add(self, rhs: Self) -> Result<Self> {
+        Fraction::checked_add(self, rhs)
+    }
+
+    fn checked_mul(self, rhs: Self) -> Result<Self> {
+        Fraction::checked_mul(self, rhs)
+    }
+
+    fn checked_div(self, rhs: Self) -> Result<Self> {
+        Fraction::checked_div(self, rhs)
+    }
+
+    fn saturating_sub(self, rhs: Self) -> Self {
+        self.checked_sub(rhs).unwrap_or(0.into())
+    }
+
+    fn ceil(self) -> Self {
+        self.cell()
+    }
+
+    fn powf(self, exponent: f64) -> Result<Self> {
+        let v = f64::from_fraction(self).powf(exponent) * 1e6;
+        if !v.is_finite() || v >= u64::MAX as f64 {
+            return Err(Error::Overflow);
+        }
+        Ok(Fraction::new(v.round() as u64, 1_000_000))
+    }
+
+    fn to_signed(self) -> Rational {
+        self.into()
+    }
+
+    fn from_signed(v: Rational) -> Result<Self> {
+        if v < Rational::ZERO {
+            return Ok(0.into());
+        }
+        v.to_fraction().ok_or(Error::Overflow)
+    }
+}
+
+impl Number for f64 {
+    type Signed = f64;
+
+    fn zero() -> Self {
+        0.0
+    }
+
+    fn from_fraction(v: Fraction) -> Self {
+        v.amount() as f64 / v.unit() as f64
+    }
+
+    fn checked_add(self, rhs: Self) -> Result<Self> {
+        finite(self + rhs)
+    }
+
+    fn checked_mul(self, rhs: Self) -> Result<Self> {
+        finite(self * rhs)
+    }
+
+    fn checked_div(self, rhs: Self) -> Result<Self> {
+        finite(self / rhs)
+    }
+
+    fn saturating_sub(self, rhs: Self) -> Self {
+        let v = self - rhs;
+        if v < EPSILON {
+            0.0
+        } else {
+            v
+        }
+    }
+
+    fn ceil(self) -> Self {
+        (self - EPSILON).ceil()
+    }
+
+    fn powf(self, exponent: f64) -> Result<Self> {
+        finite(f64::powf(self, exponent))
+    }
+
+    fn to_signed(self) -> f64 {
+        self
+    }
+
+    fn from_signed(v: f64) -> Result<Self> {
+        finite(v.max(0.0))
+    }
+}
+
+pub(crate) fn finite(v: f64) -> Result<f64> {
+    if v.is_finite() {
+        Ok(v)
+    } else {
+        Err(Error::Overflow)
+    }
+}
@@ -0,0 +1,59 @@
+use super::*;
+
+#[test]
+fn test_fraction() {
+    let v = Fraction::new(7, 3);
+    assert_eq!(Number::ceil(v), 3.into());
+    assert_eq!(Number::saturating_sub(v, 3.into()), 0.into());
+    assert_eq!(
+        Fraction::from_signed(Rational::new(-1, 3).unwrap()).unwrap(),
+        0.into()
+    );
+    assert_eq!(Fraction::new(9, 4).powf(0.5).unwrap(), Fraction::new(3, 2));
+    assert_eq!(Fraction::new(1, 3).powf(1.0).unwrap(), Fraction::new(333_333, 1_000_000));
+    let big = Fraction::new(u64::MAX, 1);
+    assert!(matches!(big.powf(2.0), Err(Error::Overflow)));
+    assert!(matches!(
+        Number::checked_mul(big, 2.into()),
+        Err(Error::Overflow)
+    ));
+}
+
+#[test]
+fn test_f64() {
+    assert_eq!(f64::from_fraction(Fraction::new(3, 2)), 1.5);
+    assert_eq!(Number::ceil(2.0 + 1e-12), 2.0);
+    assert_eq!(Number::ceil(2.1_f64), 3.0);
+    assert_eq!(Number::saturating_sub(1.0, 1.0 + 1e-12), 0.0);
+    assert!(matches!(
+        Number::checked_div(1.0, 0.0),
+        Err(Error::Overflow)
+    ));
+    assert!(matches!(
+        Number::checked_mul(f64::MAX, 2.0),
+        Err(Error::Overflow)
+    ));
+}
+
+#[test]
+fn test_calculate_as_f64() {
+    let input = "燃料 1s
+铁矿石 1s
+铁锭 = 3*铁矿石 2s
+2*塑料 = 橡胶 + 燃料 6s
+2*橡胶 = 塑料 + 燃料 6s
+产品 = 塑料 + 铁锭 6s";
+    let formula_list = crate::FormulaList::from_reader(input.as_bytes()).unwrap();
+    let target = formula_list.get_item("产品").unwrap();
+
+    let exact =
+        crate::calculate(&formula_list, &[], target.clone(), 10, &Default::default()).unwrap();
+    let float =
+        crate::calculate_as::<f64>(&formula_list, &[], target, 10, &Default::default()).unwrap();
+    assert_eq!(exact.len(), float.len());
+    for (e, f) in exact.iter().zip(float.iter()) {
+        assert_eq!(e.formula, f.formula);
+        assert!((f64::from_fraction(e.speed) - f.speed).abs() < 1e-9);
+        assert_eq!(f64::from_fraction(e.group), f.group);
+    }
+}
@@ -0,0 +1,264 @@
+//! Signed fraction for balances, where production minus consumption can be negative
+
+#[cfg(test)]
+mod test;
+
+use std::{
+    cmp::Ordering,
+    fmt::{Debug, Display},
+    ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign},
+};
+
+use crate::{Error, Fraction, Result};
+
+/// signed Fraction
+///
+/// Stored reduced in `i128` with a positive unit, the `checked_*` methods return
+/// [`Error::Overflow`] if the result does not fit while the operators panic.
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Rational(i128, i128);
+
+impl Rational {
+    pub const ZERO: Rational = Rational(0, 1);
+    pub const ONE: Rational = Rational(1, 1);
+
+    /// `amount / unit` reduced, [`Error::Overflow`] if `unit` is zero or either is `i128::MIN`,
+    /// which keeps [`Rational::abs`] and negation from overflowing.
+    pub fn new(amount: i128, unit: i128) -> Result<Self> {
+        let (Some(l), Some(r)) = (amount.checked_abs(), unit.checked_abs()) else {
+            return Err(Error::Overflow);
+        };
+        if unit == 0 {
+            return Err(Error::Overflow);
+        }
+        let g = gcd(l, r);
+        let sign = unit.signum();
+        Ok(Rational(sign * amount / g, sign * unit / g))
+    }
+
+    pub fn amount(&self) -> i128 {
+        self.0
+    }
+
+    pub fn unit(&self) -> i128 {
+        self.1
+    }
+
+    /// `None` if the value is negative or does not fit in a [`Fraction`].
+    pub fn to_fraction(self) -> Option<Fraction> {
+        let amount = u64::try_from(self.0).ok()?;
+        let unit = u64::try_from(self.1).ok()?;
+        Some(Fraction::new(amount, unit))
+    }
+
+    pub fn checked_add(self, rhs: Self) -> Result<Self> {
+        let l = self.0.checked_mul(rhs.1).ok_or(Error::Overflow)?;
+        let r = rhs.0.checked_mul(self.1).ok_or(Error::Overflow)?;
+        let amount = l.checked_add(r).ok_or(Error::Overflow)?;
+        let unit = self.1.checked_mul(rhs.1).ok_or(Error::Overflow)?;
+        Rational::new(amount, unit)
+    }
+
+    pub fn checked_sub(self, rhs: Self) -> Result<Self> {
+        self.checked_add(-rhs)
+    }
+
+    pub fn checked_mul(self, rhs: Self) -> Result<Self> {
+        // cross reduce first to keep the products small
+        let g1 = gcd(self.0.abs(), rhs.1);
+        let g2 = gcd(rhs.0.abs(), self.1);
+        let amount = (self.0 / g1)
+            .checked_mul(rhs.0 / g2)
+            .ok_or(Error::Overflow)?;
+        let unit = (self.1 / g2)
+            .checked_mul(rhs.1 / g1)
+            .ok_or(Error::Overflow)?;
+        Rational::new(amount, unit)
+    }
+
+    /// Dividing by zero is an overflow too.
+    pub fn checked_div(self, rhs: Self) -> Result<Self> {
+        if rhs.0 == 0 {
+            return Err(Error::Overflow);
+        }
+        self.checked_mul(Rational::new(rhs.1, rhs.0)?)
+    }
+
+    pub fn abs(self) -> Self {
+        Rational(self.0.abs(), self.1)
+    }
+
+    pub fn signum(self) -> i128 {
+        self.0.signum()
+    }
+
+    /// Largest integer not greater than self.
+    pub fn floor(self) -> Self {
+        Rational(self.0.div_euclid(self.1), 1)
+    }
+
+    /// Smallest integer not less than self.
+    pub fn ceil(self) -> Self {
+        -(-self).floor()
+    }
+
+    /// Nearest integer, halves are rounded away from zero.
+    pub fn round(self) -> Self {
+        let half = Rational(1, 2);
+        if self.0 < 0 {
+            -(-self + half).floor()
+        } else {
+            (self + half).floor()
+        }
+    }
+}
+
+impl Add for Rational {
+    type Output = Rational;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self.checked_add(rhs).expect("rational overflow")
+    }
+}
+
+impl AddAssign for Rational {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Rational {
+    type Output = Rational;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.checked_sub(rhs).expect("rational overflow")
+    }
+}
+
+impl SubAssign for Rational {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Neg for Rational {
+    type Output = Rational;
+
+    fn neg(self) -> Self::Output {
+        Rational(-self.0, self.1)
+    }
+}
+
+impl Mul for Rational {
+    type Output = Rational;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.checked_mul(rhs).expect("rational overflow")
+    }
+}
+
+impl Div for Rational {
+    type Output = Rational;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        if rhs.0 == 0 {
+            panic!("divide by 0");
+        }
+        self.checked_div(rhs).expect("rational overflow")
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if let (Some(l), Some(r)) = (self.0.checked_mul(other.1), other.0.checked_mul(self.1)) {
+            return l.cmp(&r);
+        }
+        // compare the integer parts, then the reciprocals of the remainders
+        let floor = self.0.div_euclid(self.1).cmp(&other.0.div_euclid(other.1));
+        if floor != Ordering::Equal {
+            return floor;
+        }
+        match (self.0.rem_euclid(self.1), other.0.rem_euclid(other.1)) {
+            (0, 0) => Ordering::Equal,
+            (0, _) => Ordering::Less,
+            (_, 0) => Ordering::Greater,
+            (l, r) => Rational(other.1, r).cmp(&Rational(self.1, l)),
+        }
+    }
+}
+
+impl From<Fraction> for Rational {
+    fn from(value: Fraction) -> Self {
+        // a fraction is reduced already
+        Rational(value.amount().into(), value.unit().into())
+    }
+}
+
+impl From<u32> for Rational {
+    fn from(value: u32) -> Self {
+        Rational(value.into(), 1)
+    }
+}
+
+impl Debug for Rational {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Rational(amount, unit) = self;
+        if *unit == 1 {
+            return write!(f, "{amount}");
+        }
+        write!(f, "{amount}/{unit}")
+    }
+}
+
+impl Display for Rational {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let amount = self.0 as f64;
+        let unit = self.1 as f64;
+        let v = amount / unit;
+        Display::fmt(&v, f)
+    }
+}
+
+/// Exact `"n/d"` string, `"n"` is read as a whole number.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Rational {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(&format_args!("{}/{}", self.0, self.1))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Rational {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let v = String::deserialize(deserializer)?;
+        let (amount, unit) = v.split_once('/').unwrap_or((&v, "1"));
+        match (amount.parse::<i128>(), unit.parse::<i128>()) {
+            (Ok(amount), Ok(unit)) => Rational::new(amount, unit).ok(),
+            _ => None,
+        }
+        .ok_or_else(|| D::Error::custom(format!("invalid fraction {v}")))
+    }
+}
+
+fn gcd(l: i128, r: i128) -> i128 {
+    let (mut l, mut r) = (l, r);
+    loop {
+        if r == 0 {
+            return if l == 0 { 1 } else { l };
+        }
+        (l, r) = (r, l % r)
+    }
+}
@@ -0,0 +1,70 @@
+use super::*;
+
+#[test]
+fn test_new() {
+    assert_eq!(Rational::new(2, -4).unwrap(), Rational(-1, 2));
+    assert_eq!(Rational::new(0, -4).unwrap(), Rational::ZERO);
+    assert_eq!(Rational::from(Fraction::new(6, 4)), Rational(3, 2));
+    assert_eq!(Rational::new(3, 4).unwrap().to_fraction(), Some(Fraction::new(3, 4)));
+    assert_eq!(Rational::new(-3, 4).unwrap().to_fraction(), None);
+    assert!(matches!(Rational::new(1, 0), Err(Error::Overflow)));
+    assert!(matches!(Rational::new(i128::MIN, 1), Err(Error::Overflow)));
+    assert!(matches!(Rational::new(1, i128::MIN), Err(Error::Overflow)));
+}
+
+#[test]
+fn test_arithmetic() {
+    assert_eq!(Rational(1, 2) - Rational(2, 3), Rational(-1, 6));
+    assert_eq!(Rational(-1, 2) + Rational(2, 3), Rational(1, 6));
+    assert_eq!(Rational(-2, 3) * Rational(3, 4), Rational(-1, 2));
+    assert_eq!(Rational(-2, 3) / Rational(-4, 3), Rational(1, 2));
+    assert_eq!(-Rational(2, 3), Rational(-2, 3));
+    assert_eq!(Rational(-2, 3).abs(), Rational(2, 3));
+
+    let max = Rational(i128::MAX, 1);
+    assert!(matches!(
+        max.checked_add(Rational::ONE),
+        Err(Error::Overflow)
+    ));
+    assert!(matches!(
+        max.checked_mul(Rational(2, 1)),
+        Err(Error::Overflow)
+    ));
+    assert!(matches!(
+        max.checked_div(Rational::ZERO),
+        Err(Error::Overflow)
+    ));
+    assert_eq!(
+        max.checked_mul(Rational(1, i128::MAX)).unwrap(),
+        Rational::ONE
+    );
+}
+
+#[test]
+fn test_cmp() {
+    assert!(Rational(-1, 2) < Rational(1, 3));
+    assert!(Rational(-1, 2) < Rational(-1, 3));
+    assert!(Rational(i128::MAX, 3) > Rational(i128::MAX, 5));
+    assert!(Rational(i128::MAX - 1, i128::MAX) > Rational(i128::MAX - 2, i128::MAX - 1));
+    assert!(Rational(-i128::MAX, 7) < Rational(-i128::MAX, 11));
+    let mut v = vec![Rational(1, 2), Rational(-3, 1), Rational(1, 3)];
+    v.sort();
+    assert_eq!(v, vec![Rational(-3, 1), Rational(1, 3), Rational(1, 2)]);
+}
+
+#[test]
+fn test_round() {
+    struct Case(Rational, i128, i128, i128);
+    let cases = vec![
+        Case(Rational(7, 2), 3, 4, 4),
+        Case(Rational(-7, 2), -4, -3, -4),
+        Case(Rational(5, 3), 1, 2, 2),
+        Case(Rational(-5, 3), -2, -1, -2),
+        Case(Rational(4, 1), 4, 4, 4),
+    ];
+    for Case(v, floor, ceil, round) in cases {
+        assert_eq!(v.floor(), Rational(floor, 1));
+        assert_eq!(v.ceil(), Rational(ceil, 1));
+        assert_eq!(v.round(), Rational(round, 1));
+    }
+}
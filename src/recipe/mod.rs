@@ -0,0 +1,151 @@
+//! Choose one formula for every item when a [`FormulaList`] has alternate recipes
+//!
+//! An item may be produced by several formulas, e.g. 铁锭 can be smelted from ore or
+//! recycled from scrap. The recipe index of a formula is its position among the formulas
+//! producing the same item, in the order they are declared with the formulas making it only
+//! as a byproduct last, see [`FormulaList::recipes`].
+
+#[cfg(test)]
+mod test;
+
+use std::{collections::HashMap, mem, rc::Rc};
+
+use crate::{Error, Formula, FormulaList, Fraction, Item, Result};
+
+/// The recipe index and formula chosen for every item.
+pub type Selected = HashMap<Rc<Item>, (usize, Rc<Formula>)>;
+
+#[derive(Debug, Clone, Default)]
+pub enum RecipeSelection {
+    /// Use the first declared formula of every item.
+    #[default]
+    First,
+    /// Use the formula consuming the least raw items per item produced.
+    Cheapest,
+    /// Use the given recipe index for the listed items, the first declared formula for others.
+    Explicit(HashMap<Rc<Item>, usize>),
+}
+
+impl RecipeSelection {
+    /// Choose the recipe index and formula for every item which has at least one formula.
+    pub fn select(
+        &self,
+        meta: &FormulaList,
+        source: &[Rc<Item>],
+    ) -> Result<Selected> {
+        let mut costs = HashMap::new();
+        let mut result = HashMap::new();
+        for item in meta.items() {
+            let recipes = meta.recipes(item);
+            if recipes.is_empty() {
+                continue;
+            }
+            let index = match self {
+                RecipeSelection::First => 0,
+                RecipeSelection::Explicit(choice) => choice.get(item).copied().unwrap_or(0),
+                RecipeSelection::Cheapest => {
+                    let mut best: Option<(usize, Fraction)> = None;
+                    for (i, f) in recipes.iter().enumerate() {
+                        let mut visiting = vec![item.clone()];
+                        let mut cut = usize::MAX;
+                        let Some(cost) = recipe_cost(
+                            meta,
+                            source,
+                            item,
+                            f,
+                            &mut costs,
+                            &mut visiting,
+                            &mut cut,
+                        )?
+                        else {
+                            continue;
+                        };
+                        if best.is_none_or(|(_, b)| cost < b) {
+                            best = Some((i, cost));
+                        }
+                    }
+                    best.map_or(0, |(i, _)| i)
+                }
+            };
+            let formula = recipes
+                .get(index)
+                .ok_or_else(|| Error::UnknownRecipe(item.clone(), index))?
+                .clone();
+            result.insert(item.clone(), (index, formula));
+        }
+        Ok(result)
+    }
+}
+
+/// Raw items consumed to produce one `item` with `formula`, byproducts are free.
+/// `None` if the formula can only be made through a loop.
+/// `cut` is lowered to the index in `visiting` of the items the search stopped at.
+fn recipe_cost(
+    meta: &FormulaList,
+    source: &[Rc<Item>],
+    item: &Item,
+    formula: &Formula,
+    costs: &mut HashMap<Rc<Item>, Option<Fraction>>,
+    visiting: &mut Vec<Rc<Item>>,
+    cut: &mut usize,
+) -> Result<Option<Fraction>> {
+    if formula.source().is_empty() {
+        return Ok(Some(1.into()));
+    }
+    let mut cost: Fraction = 0.into();
+    for v in formula.source() {
+        let Some(item_cost) = item_cost(meta, source, &v.item(), costs, visiting, cut)? else {
+            return Ok(None);
+        };
+        cost = cost.checked_add(item_cost.checked_mul(v.amount())?)?;
+    }
+    let Some(output) = formula.output(item) else {
+        return Ok(None);
+    };
+    Ok(Some(cost.checked_div(output.amount())?))
+}
+
+/// Raw items consumed to produce one `item` with its cheapest formula.
+/// The cost is only kept in `costs` if the search did not stop at an item visited before it.
+fn item_cost(
+    meta: &FormulaList,
+    source: &[Rc<Item>],
+    item: &Rc<Item>,
+    costs: &mut HashMap<Rc<Item>, Option<Fraction>>,
+    visiting: &mut Vec<Rc<Item>>,
+    cut: &mut usize,
+) -> Result<Option<Fraction>> {
+    if source.contains(item) {
+        return Ok(Some(1.into()));
+    }
+    if let Some(cost) = costs.get(item) {
+        return Ok(*cost);
+    }
+    if let Some(i) = visiting.iter().position(|x| x == item) {
+        *cut = (*cut).min(i);
+        return Ok(None);
+    }
+    let recipes = meta.recipes(item);
+    if recipes.is_empty() {
+        return Ok(Some(1.into()));
+    }
+
+    let depth = visiting.len();
+    let outer = mem::replace(cut, usize::MAX);
+    visiting.push(item.clone());
+    let mut best: Option<Fraction> = None;
+    for f in recipes.iter() {
+        if let Some(cost) = recipe_cost(meta, source, item, f, costs, visiting, cut)? {
+            if best.is_none_or(|b| cost < b) {
+                best = Some(cost);
+            }
+        }
+    }
+    visiting.pop();
+    // a loop back to the item itself does not change its cost, one to an item before it may
+    if *cut >= depth {
+        costs.insert(item.clone(), best);
+    }
+    *cut = (*cut).min(outer);
+    Ok(best)
+}
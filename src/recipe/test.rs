@@ -0,0 +1,99 @@
+use super::*;
+
+const FORMULA_LIST: &str = "铁矿石 1s
+废铁 1s
+铁锭 = 铁矿石 2s
+2*铁锭 = 铁矿石 + 3*废铁 4s
+铁棒 = 铁锭 4s";
+
+#[test]
+fn test_select() {
+    let formula_list = FormulaList::from_reader(FORMULA_LIST.as_bytes()).unwrap();
+    let ingot = formula_list.get_item("铁锭").unwrap();
+    let rod = formula_list.get_item("铁棒").unwrap();
+
+    let first = RecipeSelection::First.select(&formula_list, &[]).unwrap();
+    assert_eq!(first[&ingot].0, 0);
+    assert_eq!(first[&rod].0, 0);
+
+    // 1 raw item per ingot from ore, 4/2 raw items per ingot from scrap
+    let cheapest = RecipeSelection::Cheapest.select(&formula_list, &[]).unwrap();
+    assert_eq!(cheapest[&ingot].0, 0);
+
+    let choice = HashMap::from([(ingot.clone(), 1)]);
+    let explicit = RecipeSelection::Explicit(choice).select(&formula_list, &[]).unwrap();
+    assert_eq!(explicit[&ingot].0, 1);
+    assert_eq!(explicit[&ingot].1, formula_list.recipes(&ingot)[1]);
+    assert_eq!(explicit[&rod].0, 0);
+}
+
+#[test]
+fn test_select_cheapest() {
+    let input = "铁矿石 1s
+铁锭 = 3*铁矿石 2s
+2*铁锭 = 3*铁矿石 4s";
+    let formula_list = FormulaList::from_reader(input.as_bytes()).unwrap();
+    let ingot = formula_list.get_item("铁锭").unwrap();
+
+    let cheapest = RecipeSelection::Cheapest.select(&formula_list, &[]).unwrap();
+    assert_eq!(cheapest[&ingot].0, 1);
+}
+
+#[test]
+fn test_select_cheapest_loop() {
+    let input = "矿 1s
+P = 矿 1s
+P = 2*Y 1s
+Y = 10*矿 1s
+Y = P 1s
+Z = Y 1s
+Z = 5*矿 1s";
+    let formula_list = FormulaList::from_reader(input.as_bytes()).unwrap();
+    let z = formula_list.get_item("Z").unwrap();
+
+    // Y costs 10 while P is cut short, but 1 through P from Z
+    let cheapest = RecipeSelection::Cheapest.select(&formula_list, &[]).unwrap();
+    assert_eq!(cheapest[&z].0, 0);
+}
+
+#[test]
+fn test_select_cheapest_overflow() {
+    let input = "A 1s
+B = 1000000*A 1s
+C = 1000000*B 1s
+D = 1000000*C 1s
+E = 1000000*D 1s";
+    let formula_list = FormulaList::from_reader(input.as_bytes()).unwrap();
+    let r = RecipeSelection::Cheapest.select(&formula_list, &[]);
+    assert!(matches!(r, Err(Error::Overflow)), "{r:?}");
+}
+
+#[test]
+fn test_byproduct_recipe_index() {
+    let input = "原油 1s
+2*塑料 + 重油残渣 = 3*原油 6s
+2*重油残渣 = 原油 6s
+3*重油残渣 + 燃料 = 2*原油 6s
+燃料 = 原油 2s";
+    let formula_list = FormulaList::from_reader(input.as_bytes()).unwrap();
+    let residue = formula_list.get_item("重油残渣").unwrap();
+
+    // the recipes making it as main output come first, then the byproduct ones
+    let recipes: Vec<String> = formula_list
+        .recipes(&residue)
+        .iter()
+        .map(|x| x.to_string())
+        .collect();
+    assert_eq!(
+        recipes,
+        vec![
+            "重油残渣*2 = 原油*1 10/min",
+            "重油残渣*3 + 燃料*1 = 原油*2 10/min",
+            "塑料*2 + 重油残渣*1 = 原油*3 10/min",
+        ]
+    );
+    let first = RecipeSelection::First.select(&formula_list, &[]).unwrap();
+    assert_eq!(first[&residue].1, formula_list.recipes(&residue)[0]);
+    let fuel = formula_list.get_item("燃料").unwrap();
+    assert_eq!(formula_list.recipes(&fuel)[0].to_string(), "燃料*1 = 原油*1 30/min");
+}
@@ -0,0 +1,186 @@
+//! Rendering the diagnostics of a recipe file for a terminal
+//!
+//! A [`Report`] prints a [`Diagnostic`] like a compiler does, e.g.
+//!
+//! ```text
+//! error: Unknown char '-' at line 2, col 7, expected a time like `2s` or a rate like `@ 30/min`
+//!  --> factory.txt:2:7
+//!   |
+//! 2 | 铁棒 = 铁-锭 4s
+//!   |          ^ expected a time like `2s` or a rate like `@ 30/min`
+//!   = help: did you mean `铁锭`?
+//! ```
+//!
+//! The suggestion is the item whose name is the closest to the word around the error,
+//! leaving out the names on the line itself, which [`suggest`] also finds for a name
+//! given on the command line. A warning, e.g. of an item nothing produces, reads the same.
+
+#[cfg(test)]
+mod test;
+
+use std::{
+    fmt::{Display, Formatter},
+    rc::Rc,
+};
+
+use crate::{Diagnostic, Error, Item};
+
+/// A [`Diagnostic`] of the file at `path`, with the item it may have meant.
+pub struct Report<'a> {
+    diagnostic: &'a Diagnostic,
+    path: &'a str,
+    suggestion: Option<Rc<Item>>,
+}
+
+impl<'a> Report<'a> {
+    /// The report of `diagnostic`, suggesting one of `items`.
+    pub fn new(diagnostic: &'a Diagnostic, path: &'a str, items: &[Rc<Item>]) -> Self {
+        // the names of the line itself are not what it meant
+        let names = names(&diagnostic.source);
+        let items: Vec<Rc<Item>> = items
+            .iter()
+            .filter(|x| !names.contains(&x.to_string()))
+            .cloned()
+            .collect();
+        let suggestion = suggest(&word(diagnostic), &items);
+        Report {
+            diagnostic,
+            path,
+            suggestion,
+        }
+    }
+
+    pub fn suggestion(&self) -> Option<&Rc<Item>> {
+        self.suggestion.as_ref()
+    }
+}
+
+impl Display for Report<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let Diagnostic {
+            line,
+            span,
+            expected,
+            source,
+            ..
+        } = self.diagnostic;
+        let gutter = " ".repeat(line.to_string().len());
+        let level = if self.diagnostic.is_warning() {
+            "warning"
+        } else {
+            "error"
+        };
+        writeln!(f, "{level}: {}", self.diagnostic)?;
+        writeln!(f, "{gutter}--> {}:{line}:{}", self.path, span.start + 1)?;
+        writeln!(f, "{gutter} |")?;
+        writeln!(f, "{line} | {source}")?;
+
+        // a tab stays a tab so the caret lines up however wide the terminal shows it
+        let chars: Vec<char> = source.chars().collect();
+        let indent: String = chars
+            .iter()
+            .take(span.start)
+            .map(|c| match c {
+                '\t' => "\t".to_string(),
+                c => " ".repeat(width(*c)),
+            })
+            .collect();
+        let carets: usize = chars
+            .get(span.clone())
+            .unwrap_or_default()
+            .iter()
+            .map(|c| width(*c))
+            .sum();
+        write!(f, "{gutter} | {indent}{}", "^".repeat(carets.max(1)))?;
+        if let Some(expected) = expected {
+            write!(f, " expected {expected}")?;
+        }
+        if let Some(item) = &self.suggestion {
+            write!(f, "\n{gutter} = help: did you mean `{item}`?")?;
+        }
+        Ok(())
+    }
+}
+
+/// The item with the name closest to `name`, at most a third of its chars apart.
+/// The first one declared wins a tie.
+pub fn suggest(name: &str, items: &[Rc<Item>]) -> Option<Rc<Item>> {
+    if name.is_empty() {
+        return None;
+    }
+    let limit = (name.chars().count() / 3).max(1);
+    items
+        .iter()
+        .map(|x| (distance(name, &x.to_string()), x))
+        .filter(|(d, _)| *d <= limit)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, x)| x.clone())
+}
+
+/// The name the error is in, without the unexpected char, e.g. `铁锭` of `铁-锭`.
+fn word(diagnostic: &Diagnostic) -> String {
+    let chars: Vec<char> = diagnostic.source.chars().collect();
+    let start = diagnostic.span.start.min(chars.len());
+    let before = chars[..start]
+        .iter()
+        .rev()
+        .take_while(|c| c.is_alphanumeric())
+        .count();
+    // the name goes on after an unexpected char, otherwise it starts at the span
+    let rest = match diagnostic.error {
+        Error::UnknownChar(..) => diagnostic.span.end,
+        _ => start,
+    };
+    let after = chars
+        .get(rest..)
+        .unwrap_or_default()
+        .iter()
+        .take_while(|c| c.is_alphanumeric());
+    let v: String = chars[start - before..start].iter().chain(after).collect();
+    // an amount before the name is not a part of it
+    v.trim_start_matches(|c: char| c.is_ascii_digit())
+        .to_string()
+}
+
+/// Every name in `source`, without the amounts before them.
+fn names(source: &str) -> Vec<String> {
+    source
+        .split(|c: char| !c.is_alphanumeric())
+        .map(|x| x.trim_start_matches(|c: char| c.is_ascii_digit()))
+        .filter(|x| !x.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Levenshtein distance of the chars of `a` and `b`.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, x) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let v = (diagonal + usize::from(x != *y))
+                .min(row[j] + 1)
+                .min(row[j + 1] + 1);
+            diagonal = row[j + 1];
+            row[j + 1] = v;
+        }
+    }
+    row[b.len()]
+}
+
+/// Columns a terminal shows `c` in, 2 for the wide chars of east asian scripts.
+fn width(c: char) -> usize {
+    match c {
+        '\u{1100}'..='\u{115f}'
+        | '\u{2e80}'..='\u{a4cf}'
+        | '\u{ac00}'..='\u{d7a3}'
+        | '\u{f900}'..='\u{faff}'
+        | '\u{fe30}'..='\u{fe4f}'
+        | '\u{ff00}'..='\u{ff60}'
+        | '\u{ffe0}'..='\u{ffe6}'
+        | '\u{20000}'..='\u{3fffd}' => 2,
+        _ => 1,
+    }
+}
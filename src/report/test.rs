@@ -0,0 +1,83 @@
+use super::*;
+use crate::FormulaList;
+
+fn parse(input: &str) -> (FormulaList, Vec<Diagnostic>) {
+    FormulaList::parse(input.as_bytes()).unwrap()
+}
+
+#[test]
+fn test_render() {
+    let (list, diagnostics) = parse("铁锭 1s\n铁棒 = 铁-锭 4s\n\t螺丝 = 铁棒 6x");
+    assert_eq!(diagnostics.len(), 2);
+
+    let report = Report::new(&diagnostics[0], "f.txt", list.items());
+    assert_eq!(report.suggestion().unwrap().to_string(), "铁锭");
+    assert_eq!(
+        report.to_string(),
+        "error: Unknown char '-' at line 2, col 7, expected a time like `2s` or a rate like `@ 30/min`
+ --> f.txt:2:7
+  |
+2 | 铁棒 = 铁-锭 4s
+  |          ^ expected a time like `2s` or a rate like `@ 30/min`
+  = help: did you mean `铁锭`?"
+    );
+
+    let report = Report::new(&diagnostics[1], "f.txt", list.items());
+    assert!(report.suggestion().is_none());
+    assert!(report.to_string().ends_with(
+        "3 | \t螺丝 = 铁棒 6x\n  | \t             ^ expected `ms`, `s` or `m` after the time"
+    ));
+}
+
+#[test]
+fn test_render_without_expected() {
+    let (list, diagnostics) = parse("铁锭 1s\n铁锭 1s\n铁棒 = 0.0*铁锭 1s");
+    let report = Report::new(&diagnostics[0], "f.txt", list.items());
+    assert!(report.to_string().ends_with("2 | 铁锭 1s\n  | ^^^^^^^"));
+    let report = Report::new(&diagnostics[1], "f.txt", list.items());
+    assert!(report
+        .to_string()
+        .ends_with("3 | 铁棒 = 0.0*铁锭 1s\n  |        ^^^"));
+}
+
+#[test]
+fn test_render_suggestion() {
+    // not the name the line declares
+    let (list, diagnostics) = parse("铁棒 1s\n铁棒 = 铁-锭 4s");
+    assert!(Report::new(&diagnostics[0], "f.txt", list.items()).suggestion().is_none());
+
+    let (list, diagnostics) = parse("铁矿石 1s\n铁锭 = 铁矿石 2s\n铁棒 = 铁锭x 4s");
+    assert_eq!(diagnostics.len(), 1);
+    let report = Report::new(&diagnostics[0], "f.txt", list.items());
+    assert_eq!(
+        report.to_string(),
+        "warning: Item 铁锭x at line 3 is neither produced nor supplied
+ --> f.txt:3:6
+  |
+3 | 铁棒 = 铁锭x 4s
+  |        ^^^^^
+  = help: did you mean `铁锭`?"
+    );
+
+    let (list, diagnostics) = parse("铁矿石 1s\n铁矿 <= 60/min\n铁矿 <= 60/min");
+    let report = Report::new(&diagnostics[0], "f.txt", list.items());
+    assert!(matches!(diagnostics[0].error, Error::DuplicateSupply(_, 3)));
+    assert_eq!(report.suggestion().unwrap().to_string(), "铁矿石");
+}
+
+#[test]
+fn test_suggest() {
+    let (list, _) = parse("铁锭 1s\n铁棒 = 铁锭 4s\n加强铁板 = 铁棒 12s");
+    let items = list.items();
+    assert_eq!(suggest("铁棒子", items).unwrap().to_string(), "铁棒");
+    // a tie goes to the first item
+    assert_eq!(suggest("铁捧", items).unwrap().to_string(), "铁锭");
+    assert_eq!(suggest("加强铁", items).unwrap().to_string(), "加强铁板");
+    assert!(suggest("铜线", items).is_none());
+    assert!(suggest("", items).is_none());
+
+    assert_eq!(distance("kitten", "sitting"), 3);
+    assert_eq!(distance("", "铁棒"), 2);
+    assert_eq!(width('铁'), 2);
+    assert_eq!(width('a'), 1);
+}
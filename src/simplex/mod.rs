@@ -0,0 +1,355 @@
+//! Exact linear programming with the simplex method
+//!
+//! Minimize `objective · x` subject to a list of [`Constraint`] and `x >= 0`.
+//! The two-phase tableau method with Bland's rule is used over a [`Field`], either the exact
+//! [`Rational`] or `f64`, which is fine for the small problems built from a formula list.
+
+#[cfg(test)]
+mod test;
+
+use std::{cmp::Ordering, fmt::Debug, ops::Neg};
+
+use crate::{number::finite, Error, Fraction, Number, Rational, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Relation {
+    /// `coefficients · x <= value`
+    Le,
+    /// `coefficients · x >= value`
+    Ge,
+    /// `coefficients · x == value`
+    Eq,
+}
+
+/// differences of `f64` smaller than this are rounding errors
+pub(crate) const EPSILON: f64 = 1e-9;
+
+/// Signed numbers the simplex works on, the arithmetic returns [`Error::Overflow`]
+/// instead of panicking.
+pub trait Field: Copy + PartialOrd + Debug + Neg<Output = Self> {
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn checked_add(self, rhs: Self) -> Result<Self>;
+    fn checked_sub(self, rhs: Self) -> Result<Self>;
+    fn checked_mul(self, rhs: Self) -> Result<Self>;
+    /// dividing by zero is an overflow too
+    fn checked_div(self, rhs: Self) -> Result<Self>;
+
+    /// the non-negative `v`
+    fn from_unsigned(v: Fraction) -> Self;
+    /// greater than zero, ignoring rounding errors
+    fn is_positive(self) -> bool;
+    /// less than zero, ignoring rounding errors
+    fn is_negative(self) -> bool;
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Constraint<F> {
+    pub coefficients: Vec<F>,
+    pub relation: Relation,
+    pub value: F,
+}
+
+/// Minimize `objective · x`, returns the optimal `x`.
+pub(crate) fn minimize<F: Field>(objective: &[F], constraints: &[Constraint<F>]) -> Result<Vec<F>> {
+    let n = objective.len();
+    let mut tableau = Tableau::new(n, constraints);
+
+    let artificial: Vec<F> = (0..tableau.columns())
+        .map(|j| {
+            if tableau.is_artificial(j) {
+                F::ONE
+            } else {
+                F::ZERO
+            }
+        })
+        .collect();
+    tableau.optimize(&artificial, true)?;
+    if tableau.value(&artificial)?.is_positive() {
+        return Err(Error::Infeasible);
+    }
+    tableau.drive_out_artificial()?;
+
+    let mut cost = objective.to_vec();
+    cost.resize(tableau.columns(), F::ZERO);
+    tableau.optimize(&cost, false)?;
+    Ok(tableau.solution(n))
+}
+
+struct Tableau<F> {
+    /// `rows[i][j]` for every constraint, the last column is the value
+    rows: Vec<Vec<F>>,
+    basis: Vec<usize>,
+    /// columns from here on are artificial
+    artificial: usize,
+    columns: usize,
+}
+
+impl<F: Field> Tableau<F> {
+    fn new(n: usize, constraints: &[Constraint<F>]) -> Tableau<F> {
+        // make every value non-negative first
+        let constraints: Vec<Constraint<F>> = constraints
+            .iter()
+            .map(|c| {
+                if !c.value.is_negative() {
+                    return c.clone();
+                }
+                Constraint {
+                    coefficients: c.coefficients.iter().map(|x| -*x).collect(),
+                    relation: match c.relation {
+                        Relation::Le => Relation::Ge,
+                        Relation::Ge => Relation::Le,
+                        Relation::Eq => Relation::Eq,
+                    },
+                    value: -c.value,
+                }
+            })
+            .collect();
+        let slack = constraints
+            .iter()
+            .filter(|x| x.relation != Relation::Eq)
+            .count();
+        let artificial = constraints
+            .iter()
+            .filter(|x| x.relation != Relation::Le)
+            .count();
+        let width = n + slack + artificial + 1;
+
+        let mut rows = Vec::new();
+        let mut basis = Vec::new();
+        let (mut slack_i, mut artificial_i) = (n, n + slack);
+        for c in constraints {
+            let mut row = vec![F::ZERO; width];
+            row[..n].copy_from_slice(&c.coefficients);
+            row[width - 1] = c.value;
+            match c.relation {
+                Relation::Le => {
+                    row[slack_i] = F::ONE;
+                    basis.push(slack_i);
+                    slack_i += 1;
+                }
+                Relation::Ge => {
+                    row[slack_i] = -F::ONE;
+                    row[artificial_i] = F::ONE;
+                    basis.push(artificial_i);
+                    slack_i += 1;
+                    artificial_i += 1;
+                }
+                Relation::Eq => {
+                    row[artificial_i] = F::ONE;
+                    basis.push(artificial_i);
+                    artificial_i += 1;
+                }
+            }
+            rows.push(row);
+        }
+        Tableau {
+            rows,
+            basis,
+            artificial: n + slack,
+            columns: width - 1,
+        }
+    }
+
+    fn columns(&self) -> usize {
+        self.columns
+    }
+
+    fn is_artificial(&self, j: usize) -> bool {
+        j >= self.artificial
+    }
+
+    fn value(&self, cost: &[F]) -> Result<F> {
+        let last = self.columns();
+        self.rows
+            .iter()
+            .zip(self.basis.iter())
+            .try_fold(F::ZERO, |v, (row, &b)| v.checked_add(cost[b].checked_mul(row[last])?))
+    }
+
+    fn solution(&self, n: usize) -> Vec<F> {
+        let last = self.columns();
+        let mut x = vec![F::ZERO; n];
+        for (row, &b) in self.rows.iter().zip(self.basis.iter()) {
+            if b < n {
+                x[b] = row[last];
+            }
+        }
+        x
+    }
+
+    /// Pivot until `cost` is minimal, artificial columns only enter in phase one.
+    fn optimize(&mut self, cost: &[F], phase_one: bool) -> Result<()> {
+        let last = self.columns();
+        loop {
+            let mut entering = None;
+            for j in 0..last {
+                if !phase_one && self.is_artificial(j) {
+                    continue;
+                }
+                let reduced = self
+                    .rows
+                    .iter()
+                    .zip(self.basis.iter())
+                    .try_fold(cost[j], |v, (row, &b)| v.checked_sub(cost[b].checked_mul(row[j])?))?;
+                if reduced.is_negative() {
+                    entering = Some(j);
+                    break;
+                }
+            }
+            let Some(entering) = entering else {
+                return Ok(());
+            };
+
+            let mut leaving: Option<(usize, F)> = None;
+            for (i, row) in self.rows.iter().enumerate() {
+                if !row[entering].is_positive() {
+                    continue;
+                }
+                let ratio = row[last].checked_div(row[entering])?;
+                let better = match leaving {
+                    None => true,
+                    Some((l, r)) => match compare(ratio, r)? {
+                        Ordering::Less => true,
+                        Ordering::Equal => self.basis[i] < self.basis[l],
+                        Ordering::Greater => false,
+                    },
+                };
+                if better {
+                    leaving = Some((i, ratio));
+                }
+            }
+            let Some((leaving, _)) = leaving else {
+                return Err(Error::Unbounded);
+            };
+            self.pivot(leaving, entering)?;
+        }
+    }
+
+    /// Remove artificial columns left in the basis at zero after phase one.
+    fn drive_out_artificial(&mut self) -> Result<()> {
+        let mut i = 0;
+        while i < self.rows.len() {
+            if !self.is_artificial(self.basis[i]) {
+                i += 1;
+                continue;
+            }
+            let column = (0..self.artificial).find(|&j| is_nonzero(self.rows[i][j]));
+            match column {
+                Some(j) => {
+                    self.pivot(i, j)?;
+                    i += 1;
+                }
+                None => {
+                    // redundant constraint
+                    self.rows.remove(i);
+                    self.basis.remove(i);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn pivot(&mut self, row: usize, column: usize) -> Result<()> {
+        let p = self.rows[row][column];
+        for x in self.rows[row].iter_mut() {
+            *x = x.checked_div(p)?;
+        }
+        let pivot_row = self.rows[row].clone();
+        for (i, r) in self.rows.iter_mut().enumerate() {
+            if i == row || !is_nonzero(r[column]) {
+                continue;
+            }
+            let factor = r[column];
+            for (x, p) in r.iter_mut().zip(pivot_row.iter()) {
+                *x = x.checked_sub(factor.checked_mul(*p)?)?;
+            }
+        }
+        self.basis[row] = column;
+        Ok(())
+    }
+}
+
+fn is_nonzero<F: Field>(v: F) -> bool {
+    v.is_positive() || v.is_negative()
+}
+
+/// Order ignoring rounding errors.
+fn compare<F: Field>(l: F, r: F) -> Result<Ordering> {
+    let d = l.checked_sub(r)?;
+    let v = if d.is_negative() {
+        Ordering::Less
+    } else if d.is_positive() {
+        Ordering::Greater
+    } else {
+        Ordering::Equal
+    };
+    Ok(v)
+}
+
+impl Field for f64 {
+    const ZERO: f64 = 0.0;
+    const ONE: f64 = 1.0;
+
+    fn checked_add(self, rhs: Self) -> Result<Self> {
+        finite(self + rhs)
+    }
+
+    fn checked_sub(self, rhs: Self) -> Result<Self> {
+        finite(self - rhs)
+    }
+
+    fn checked_mul(self, rhs: Self) -> Result<Self> {
+        finite(self * rhs)
+    }
+
+    fn checked_div(self, rhs: Self) -> Result<Self> {
+        finite(self / rhs)
+    }
+
+    fn from_unsigned(v: Fraction) -> Self {
+        <f64 as Number>::from_fraction(v)
+    }
+
+    fn is_positive(self) -> bool {
+        self > EPSILON
+    }
+
+    fn is_negative(self) -> bool {
+        self < -EPSILON
+    }
+}
+
+impl Field for Rational {
+    const ZERO: Rational = Rational::ZERO;
+    const ONE: Rational = Rational::ONE;
+
+    fn checked_add(self, rhs: Self) -> Result<Self> {
+        Rational::checked_add(self, rhs)
+    }
+
+    fn checked_sub(self, rhs: Self) -> Result<Self> {
+        Rational::checked_sub(self, rhs)
+    }
+
+    fn checked_mul(self, rhs: Self) -> Result<Self> {
+        Rational::checked_mul(self, rhs)
+    }
+
+    fn checked_div(self, rhs: Self) -> Result<Self> {
+        Rational::checked_div(self, rhs)
+    }
+
+    fn from_unsigned(v: Fraction) -> Self {
+        v.into()
+    }
+
+    fn is_positive(self) -> bool {
+        self.signum() > 0
+    }
+
+    fn is_negative(self) -> bool {
+        self.signum() < 0
+    }
+}
@@ -0,0 +1,60 @@
+use super::*;
+
+fn r(v: i128) -> Rational {
+    Rational::new(v, 1).unwrap()
+}
+
+fn constraint(coefficients: &[i128], relation: Relation, value: i128) -> Constraint<Rational> {
+    Constraint {
+        coefficients: coefficients.iter().map(|x| r(*x)).collect(),
+        relation,
+        value: r(value),
+    }
+}
+
+#[test]
+fn test_minimize() {
+    // min x + y, x + 2y >= 4, 3x + y >= 6
+    let constraints = vec![
+        constraint(&[1, 2], Relation::Ge, 4),
+        constraint(&[3, 1], Relation::Ge, 6),
+    ];
+    let x = minimize(&[r(1), r(1)], &constraints).unwrap();
+    assert_eq!(x, vec![Rational::new(8, 5).unwrap(), Rational::new(6, 5).unwrap()]);
+
+    // max 3x + 2y, x + y <= 4, x + 3y <= 6, x <= 3
+    let constraints = vec![
+        constraint(&[1, 1], Relation::Le, 4),
+        constraint(&[1, 3], Relation::Le, 6),
+        constraint(&[1, 0], Relation::Le, 3),
+    ];
+    let x = minimize(&[r(-3), r(-2)], &constraints).unwrap();
+    assert_eq!(x, vec![r(3), r(1)]);
+
+    // redundant equality and negative value
+    let constraints = vec![
+        constraint(&[1, 1], Relation::Eq, 2),
+        constraint(&[-2, -2], Relation::Eq, -4),
+        constraint(&[1, -1], Relation::Le, -1),
+    ];
+    let x = minimize(&[r(1), r(0)], &constraints).unwrap();
+    assert_eq!(x, vec![r(0), r(2)]);
+}
+
+#[test]
+fn test_minimize_error() {
+    let constraints = vec![
+        constraint(&[1, 1], Relation::Le, 1),
+        constraint(&[1, 1], Relation::Ge, 2),
+    ];
+    assert!(matches!(
+        minimize(&[r(1), r(1)], &constraints),
+        Err(Error::Infeasible)
+    ));
+
+    let constraints = vec![constraint(&[1, -1], Relation::Le, 1)];
+    assert!(matches!(
+        minimize(&[r(-1), r(0)], &constraints),
+        Err(Error::Unbounded)
+    ));
+}
@@ -0,0 +1,67 @@
+//! Everything a caller needs to know about a list of plans
+//!
+//! [`PlanResult`] keeps the plans of [`crate::calculate`] together with the totals derived
+//! from them, so a caller does not walk the plans again.
+
+#[cfg(test)]
+mod test;
+
+use std::rc::Rc;
+
+use crate::{
+    logistics::flows, supply::produced, Buildings, Flow, Formula, FormulaList, Fraction, Item,
+    Number, Plan, Result,
+};
+
+#[derive(Debug)]
+pub struct PlanResult<N: Number = Fraction> {
+    pub plans: Vec<Plan<N>>,
+    /// raw items per minute, the outputs of formulas without inputs not supplied from outside
+    pub raw: Vec<(Rc<Item>, N)>,
+    /// items per minute supplied from outside
+    pub source: Vec<(Rc<Item>, N)>,
+    /// buildings running the formula of every plan
+    pub formulas: Vec<(Rc<Formula>, N)>,
+    pub buildings: Buildings<N>,
+    /// items left over or missing per minute, see [`Plan::balance`]
+    pub balance: Vec<(Rc<Item>, N::Signed)>,
+    /// which plan feeds which
+    pub edges: Vec<Flow<N>>,
+}
+
+impl<N: Number> PlanResult<N> {
+    /// Derive the totals of `plans` calculated with `source` items.
+    pub fn new(meta: &FormulaList, source: &[Rc<Item>], plans: Vec<Plan<N>>) -> Result<Self> {
+        let mut raw = Vec::new();
+        for item in meta.items() {
+            if source.contains(item) {
+                continue;
+            }
+            let is_raw = plans
+                .iter()
+                .any(|x| x.formula.source().is_empty() && x.formula.output(item).is_some());
+            if is_raw {
+                raw.push((item.clone(), produced(&plans, item)?));
+            }
+        }
+        let edges = flows(meta, source, &plans)?;
+        let source = source
+            .iter()
+            .map(|x| Ok((x.clone(), produced(&plans, x)?)))
+            .collect::<Result<Vec<_>>>()?;
+        let formulas = plans.iter().map(|x| (x.formula.clone(), x.group)).collect();
+        let balance = plans
+            .iter()
+            .flat_map(|x| x.balance.iter().cloned())
+            .collect();
+        Ok(PlanResult {
+            buildings: Buildings::new(&plans)?,
+            plans,
+            raw,
+            source,
+            formulas,
+            balance,
+            edges,
+        })
+    }
+}
@@ -0,0 +1,48 @@
+use super::*;
+use crate::calculate;
+
+#[test]
+fn test_plan_result() {
+    let input = "铁矿石 1s
+原油 1s
+铁锭 = 铁矿石 2s
+2*塑料 + 重油残渣 = 3*原油 6s
+电路板 = 2*铁锭 + 塑料 4s";
+    let formula_list = FormulaList::from_reader(input.as_bytes()).unwrap();
+    let item = |name: &str| formula_list.get_item(name).unwrap();
+    let target = item("电路板");
+
+    let plans = calculate(&formula_list, &[], target.clone(), 30, &Default::default()).unwrap();
+    let result = PlanResult::new(&formula_list, &[], plans).unwrap();
+    assert_eq!(
+        result.raw,
+        vec![(item("铁矿石"), 60.into()), (item("原油"), 45.into())]
+    );
+    assert!(result.source.is_empty());
+    assert_eq!(result.formulas.len(), result.plans.len());
+    assert_eq!(
+        result.buildings.total,
+        result
+            .formulas
+            .iter()
+            .fold(Fraction::from(0), |v, (_, x)| v + *x)
+    );
+    assert_eq!(result.balance, vec![(item("重油残渣"), 15.into())]);
+
+    // every consumed item comes from a plan
+    let board = result.plans.len() - 1;
+    let mut inputs: Vec<_> = result
+        .edges
+        .iter()
+        .filter(|x| x.to == board)
+        .map(|x| (x.item.clone(), x.from.is_some()))
+        .collect();
+    inputs.sort_by_key(|x| x.0.to_string());
+    assert_eq!(inputs, vec![(item("塑料"), true), (item("铁锭"), true)]);
+
+    let source = [item("铁锭")];
+    let plans = calculate(&formula_list, &source, target, 30, &Default::default()).unwrap();
+    let result = PlanResult::new(&formula_list, &source, plans).unwrap();
+    assert_eq!(result.raw, vec![(item("原油"), 45.into())]);
+    assert_eq!(result.source, vec![(item("铁锭"), 60.into())]);
+}
@@ -0,0 +1,80 @@
+//! Limits on the supply of raw items
+//!
+//! A plan is checked against the `item <= n/min` lines of the [`FormulaList`] once calculated.
+//! Plans of the graph solver grow linearly with the rate of the target, so its maximum rate
+//! comes from a plan of 1/min, the linear solver finds it with the [`simplex`].
+//!
+//! [`simplex`]: crate::simplex
+
+#[cfg(test)]
+mod test;
+
+use std::rc::Rc;
+
+use crate::{
+    graph, linear, Buildings, Error, FormulaList, Fraction, Item, Number, Options, Plan, Result,
+    Solver,
+};
+
+/// Items per minute of `item` produced by all plans.
+pub fn produced<N: Number>(plans: &[Plan<N>], item: &Item) -> Result<N> {
+    let mut total = N::zero();
+    for plan in plans {
+        let formula = &plan.formula;
+        let Some(output) = formula.output(item) else {
+            continue;
+        };
+        let crafts = plan.crafts()?;
+        let v = crafts.checked_mul(N::from_fraction(output.amount()))?;
+        total = total.checked_add(v)?;
+    }
+    Ok(total)
+}
+
+/// Fails with the items the plans produce more of than their supply.
+pub(crate) fn check<N: Number>(meta: &FormulaList, plans: &[Plan<N>]) -> Result<()> {
+    let mut items = Vec::new();
+    for item in meta.limited() {
+        let limit = N::from_fraction(meta.supply(&item).unwrap());
+        if produced(plans, &item)? > limit {
+            items.push(item);
+        }
+    }
+    if !items.is_empty() {
+        return Err(Error::SupplyLimit(items));
+    }
+    Ok(())
+}
+
+pub(crate) fn max_rate(
+    meta: &FormulaList,
+    source: &[Rc<Item>],
+    target: Rc<Item>,
+    options: &Options,
+) -> Result<Fraction> {
+    if let Solver::Linear(_) = options.solver {
+        let v = linear::max_rate(meta, source, target, options.power_limit)?;
+        return v.to_fraction().ok_or(Error::Overflow);
+    }
+
+    let plans: Vec<Plan> = graph::calculate(meta, source, &[(target, 1.into())], &options.selection, options.trace.as_deref())?;
+    let mut limits = Vec::new();
+    for item in meta.limited() {
+        limits.push((meta.supply(&item).unwrap(), produced(&plans, &item)?));
+    }
+    if let Some(limit) = options.power_limit {
+        limits.push((limit.into(), Buildings::new(&plans)?.power));
+    }
+
+    let mut best: Option<Fraction> = None;
+    for (limit, used) in limits {
+        if used == 0.into() {
+            continue;
+        }
+        let v = limit.checked_div(used)?;
+        if best.is_none_or(|x| v < x) {
+            best = Some(v);
+        }
+    }
+    best.ok_or(Error::Unbounded)
+}
@@ -0,0 +1,58 @@
+use super::*;
+use crate::{calculate, Objective};
+
+const INPUT: &str = "铁矿石 1s
+铜矿石 1s
+铁锭 = 铁矿石 2s
+铜锭 = 铜矿石 2s
+电线 = 铁锭 + 2*铜锭 4s
+电线 = 3*铁锭 4s
+铁矿石 <= 480/min
+铜矿石 <= 600/min";
+
+#[test]
+fn test_check() {
+    let formula_list = FormulaList::from_reader(INPUT.as_bytes()).unwrap();
+    let target = formula_list.get_item("电线").unwrap();
+    let options = Options::default();
+
+    let plans = calculate(&formula_list, &[], target.clone(), 300, &options).unwrap();
+    let copper = formula_list.get_item("铜矿石").unwrap();
+    assert_eq!(produced(&plans, &copper).unwrap(), 600.into());
+
+    let r = calculate(&formula_list, &[], target.clone(), 400, &options);
+    assert!(matches!(r, Err(Error::SupplyLimit(items)) if items == vec![copper.clone()]));
+    let r = calculate(&formula_list, &[], target, 500, &options);
+    assert!(matches!(r, Err(Error::SupplyLimit(items)) if items.len() == 2));
+}
+
+#[test]
+fn test_max_rate() {
+    let formula_list = FormulaList::from_reader(INPUT.as_bytes()).unwrap();
+    let target = formula_list.get_item("电线").unwrap();
+
+    let graph = Options::default();
+    let r = max_rate(&formula_list, &[], target.clone(), &graph).unwrap();
+    assert_eq!(r, 300.into());
+
+    // 300 wires from copper and iron, 60 more from iron only
+    let linear = Options {
+        solver: Solver::Linear(Objective::RawResource),
+        ..Default::default()
+    };
+    let r = max_rate(&formula_list, &[], target.clone(), &linear).unwrap();
+    assert_eq!(r, 360.into());
+    assert!(calculate(&formula_list, &[], target.clone(), 360, &linear).is_ok());
+    let r = calculate(&formula_list, &[], target.clone(), 361, &linear);
+    assert!(matches!(r, Err(Error::SupplyLimit(_))));
+
+    // nothing limits ingots supplied from outside
+    let source = [
+        formula_list.get_item("铁锭").unwrap(),
+        formula_list.get_item("铜锭").unwrap(),
+    ];
+    for options in [graph, linear] {
+        let r = max_rate(&formula_list, &source, target.clone(), &options);
+        assert!(matches!(r, Err(Error::Unbounded)));
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{formula::FormulaList, calculate};
+use crate::{
+    calculate, calculate_many, formula::FormulaList, maximize, Fraction, Number, Options, Plan,
+    RateUnit, RecipeSelection,
+};
 
 #[test]
 fn test1() {
@@ -32,11 +35,233 @@ fn test1() {
 2*多功能框架 = 模块化框架 + 12*钢梁 24s
 自动线路 = 定子 + 20*电缆 24s";
     let formula_list = FormulaList::from_reader(formula_list.as_bytes()).unwrap();
-    let source: Vec<std::rc::Rc<crate::Item>> = vec![formula_list.get_item("铁锭")];
-    let target= formula_list.get_item("重型模块化框架");
+    let source: Vec<std::rc::Rc<crate::Item>> = vec![formula_list.get_item("铁锭").unwrap()];
+    let target= formula_list.get_item("重型模块化框架").unwrap();
 
-    let r = calculate(&formula_list, &source, target, 4);
+    let r = calculate(&formula_list, &source, target, 4, &Options::default()).unwrap();
     for v in r{
         println!("{v}")
     }
 }
+
+#[test]
+fn test_alternate_recipe() {
+    let formula_list = "铁矿石 1s
+废铁 1s
+铁锭 = 铁矿石 2s
+2*铁锭 = 铁矿石 + 3*废铁 4s
+铁棒 = 铁锭 4s";
+    let formula_list = FormulaList::from_reader(formula_list.as_bytes()).unwrap();
+    let ingot = formula_list.get_item("铁锭").unwrap();
+    let target = formula_list.get_item("铁棒").unwrap();
+
+    let choice = std::collections::HashMap::from([(ingot.clone(), 1)]);
+    let options = Options {
+        selection: RecipeSelection::Explicit(choice),
+        ..Default::default()
+    };
+    let r = calculate(&formula_list, &[], target, 15, &options).unwrap();
+    let plan = r.iter().find(|x| x.formula.target().item() == ingot).unwrap();
+    assert_eq!(plan.recipe, 1);
+    assert_eq!(plan.formula, formula_list.recipes(&ingot)[1]);
+    assert!(r.iter().any(|x| x.formula.target().item().to_string() == "废铁"));
+}
+
+#[test]
+fn test_byproduct() {
+    let formula_list = "原油 1s
+2*塑料 + 重油残渣 = 3*原油 6s
+2*重油残渣 = 原油 6s
+产品 = 塑料 + 重油残渣 6s";
+    let formula_list = FormulaList::from_reader(formula_list.as_bytes()).unwrap();
+    let plastic = formula_list.get_item("塑料").unwrap();
+    let residue = formula_list.get_item("重油残渣").unwrap();
+    let oil = formula_list.get_item("原油").unwrap();
+
+    // the byproduct covers half of the residue, the rest comes from its own recipe
+    let choice = std::collections::HashMap::from([(residue.clone(), 0)]);
+    let options = Options {
+        selection: RecipeSelection::Explicit(choice),
+        ..Default::default()
+    };
+    let r = calculate(&formula_list, &[], formula_list.get_item("产品").unwrap(), 10, &options).unwrap();
+    let speed = |item: &std::rc::Rc<crate::Item>| {
+        r.iter().find(|x| x.formula.target().item() == *item).unwrap().speed
+    };
+    assert_eq!(speed(&plastic), 10.into());
+    assert_eq!(speed(&residue), 5.into());
+    assert_eq!(speed(&oil), crate::Fraction::new(35, 2));
+    assert!(r.iter().all(|x| x.balance.is_empty()));
+
+    // only plastic is required, the residue is left over
+    let r = calculate(&formula_list, &[], plastic.clone(), 10, &Options::default()).unwrap();
+    assert_eq!(r.len(), 2);
+    let plan = r.iter().find(|x| x.formula.target().item() == plastic).unwrap();
+    assert_eq!(plan.balance, vec![(residue.clone(), 5.into())]);
+    assert!(plan.to_string().ends_with(" (surplus 重油残渣*5/min)"));
+
+    let mut plan: Plan = Plan::new(&formula_list, plan.formula.clone(), 0, 1.into()).unwrap();
+    plan.balance.push((residue, crate::Rational::new(-1, 2).unwrap()));
+    assert!(plan.to_string().ends_with(" (shortfall 重油残渣*1/2/min)"));
+}
+
+#[test]
+fn test_rate_unit() {
+    let formula_list = "原油 1s
+2*塑料 + 重油残渣 = 3*原油 6s";
+    let formula_list = FormulaList::from_reader(formula_list.as_bytes()).unwrap();
+    let plastic = formula_list.get_item("塑料").unwrap();
+    let options = Options {
+        rate_unit: RateUnit::Hour,
+        ..Default::default()
+    };
+    let r = calculate(&formula_list, &[], plastic.clone(), 10, &options).unwrap();
+    let plan = r.iter().find(|x| x.formula.target().item() == plastic).unwrap();
+    // the fields stay per minute
+    assert_eq!(plan.speed, 10.into());
+    let v = plan.to_string();
+    assert!(v.contains(" speed=600.00/h "), "{v}");
+    assert!(v.contains("塑料*600 + 重油残渣*300 = 原油*900"), "{v}");
+    assert!(v.ends_with(" (surplus 重油残渣*300/h)"), "{v}");
+
+    let options = Options {
+        rate_unit: RateUnit::Second,
+        ..Default::default()
+    };
+    let r = calculate(&formula_list, &[], plastic.clone(), 10, &options).unwrap();
+    let plan = r.iter().find(|x| x.formula.target().item() == plastic).unwrap();
+    assert!(plan.to_string().ends_with(" (surplus 重油残渣*1/12/s)"));
+}
+
+#[test]
+fn test_overclock() {
+    let formula_list = "铁锭 1s\n铁棒 = 铁锭 4s @构筑站\n@构筑站 4MW";
+    let formula_list = FormulaList::from_reader(formula_list.as_bytes()).unwrap();
+    let target = formula_list.get_item("铁棒").unwrap();
+    let source = [formula_list.get_item("铁锭").unwrap()];
+    let run = |overclock| {
+        let options = Options {
+            overclock,
+            ..Default::default()
+        };
+        calculate(&formula_list, &source, target.clone(), 40, &options).unwrap()
+    };
+
+    let plan = run(None).pop().unwrap();
+    assert_eq!((plan.group, plan.rate, plan.clock), (3.into(), Fraction::new(8, 9), 1.into()));
+    assert_eq!(plan.power, Fraction::new(32, 3));
+
+    // 3 buildings at 8/9 of the clock
+    let plan = run(Some(100)).pop().unwrap();
+    assert_eq!((plan.group, plan.rate, plan.clock), (3.into(), 1.into(), Fraction::new(8, 9)));
+    assert!(plan.power < Fraction::new(32, 3));
+    assert!(plan.to_string().contains(" clock= 88.89%"));
+
+    // 2 buildings at 4/3 of the clock, power grows faster than the speed
+    let plan = run(Some(250)).pop().unwrap();
+    assert_eq!((plan.group, plan.clock), (2.into(), Fraction::new(4, 3)));
+    assert_eq!(plan.power, Fraction::new(8, 1) * Fraction::new(4, 3).powf(1.321928).unwrap());
+    assert!(plan.power > Fraction::new(32, 3));
+}
+
+#[test]
+fn test_maximize() {
+    let formula_list = "铁矿石 1s
+铁锭 = 铁矿石 2s
+2*铁板 = 3*铁锭 6s
+铁棒 = 铁锭 4s
+4*螺丝 = 铁棒 6s
+加强铁板 = 6*铁板 + 12*螺丝 12s
+铁矿石 <= 120/min";
+    let formula_list = FormulaList::from_reader(formula_list.as_bytes()).unwrap();
+    let target = formula_list.get_item("加强铁板").unwrap();
+    let ingot = formula_list.get_item("铁锭").unwrap();
+
+    // 12 ingots for each plate, the ore limit does not matter for supplied ingots
+    let supply = [(ingot.clone(), 250)];
+    let (rate, plans) = maximize(&formula_list, &supply, target.clone(), &Options::default()).unwrap();
+    assert_eq!(rate, Fraction::new(125, 6));
+    assert_eq!(plans.last().unwrap().speed, rate);
+    assert_eq!(crate::supply::produced(&plans, &ingot).unwrap(), 250.into());
+
+    // the ore is the bottleneck if mined
+    let ore = formula_list.get_item("铁矿石").unwrap();
+    let (rate, _) = maximize(&formula_list, &[(ore, 600)], target.clone(), &Options::default()).unwrap();
+    assert_eq!(rate, 10.into());
+}
+
+#[test]
+fn test_calculate_many() {
+    let formula_list = "铁矿石 1s
+铁锭 = 铁矿石 2s
+2*铁板 = 3*铁锭 6s
+铁棒 = 铁锭 4s
+4*螺丝 = 铁棒 6s
+加强铁板 = 6*铁板 + 12*螺丝 12s
+转子 = 5*铁棒 + 25*螺丝 15s";
+    let formula_list = FormulaList::from_reader(formula_list.as_bytes()).unwrap();
+    let item = |name: &str| formula_list.get_item(name).unwrap();
+    let linear = Options {
+        solver: crate::Solver::Linear(crate::Objective::RawResource),
+        ..Default::default()
+    };
+
+    // screws and rods for both targets come from one plan each
+    let targets = [(item("加强铁板"), 10), (item("转子"), 5)];
+    for options in [Options::default(), linear] {
+        let r = calculate_many(&formula_list, &[], &targets, &options).unwrap();
+        assert_eq!(r.len(), 7);
+        let speed = |name: &str| {
+            r.iter()
+                .find(|x| x.formula.target().item() == item(name))
+                .unwrap()
+                .speed
+        };
+        assert_eq!(speed("加强铁板"), 10.into());
+        assert_eq!(speed("转子"), 5.into());
+        assert_eq!(speed("螺丝"), 245.into());
+        assert_eq!(speed("铁棒"), Fraction::new(345, 4));
+    }
+
+    // a target also consumed by another one
+    let targets = [(item("加强铁板"), 10), (item("螺丝"), 10)];
+    let r = calculate_many(&formula_list, &[], &targets, &Options::default()).unwrap();
+    let plan = r.iter().find(|x| x.formula.target().item() == item("螺丝")).unwrap();
+    assert_eq!(plan.speed, 130.into());
+}
+
+#[test]
+fn test_calculate_error() {
+    let formula_list = "20*生物质 = 4*木头 4s";
+    let formula_list = FormulaList::from_reader(formula_list.as_bytes()).unwrap();
+    let target = formula_list.get_item("生物质").unwrap();
+
+    let r = calculate(&formula_list, &[], target.clone(), 10, &Options::default());
+    assert!(matches!(r, Err(crate::Error::MissingRecipe(item)) if item.to_string() == "木头"));
+
+    let choice = std::collections::HashMap::from([(target.clone(), 1)]);
+    let options = Options {
+        selection: RecipeSelection::Explicit(choice),
+        ..Default::default()
+    };
+    let r = calculate(&formula_list, &[], target, 10, &options);
+    assert!(matches!(r, Err(crate::Error::UnknownRecipe(_, 1))));
+}
+
+#[test]
+fn test_calculate_overflow() {
+    let formula_list = "C 1s
+A = 1.000000000000000001*B + C 1s
+1.000000000000000007*B = 0.999999999999999989*A 1s
+D = 1.000000000000000019*A 1s";
+    let formula_list = FormulaList::from_reader(formula_list.as_bytes()).unwrap();
+    let target = formula_list.get_item("D").unwrap();
+    let linear = Options {
+        solver: crate::Solver::Linear(crate::Objective::RawResource),
+        ..Default::default()
+    };
+    for options in [Options::default(), linear] {
+        let r = calculate(&formula_list, &[], target.clone(), 7, &options);
+        assert!(matches!(r, Err(crate::Error::Overflow)), "{r:?}");
+    }
+}
@@ -0,0 +1,38 @@
+//! Tracing the steps of the graph solver
+//!
+//! Set [`crate::Options::trace`] to see which formula each required item uses, how many
+//! formulas consume it and the crafts calculated for it.
+
+#[cfg(test)]
+mod test;
+
+use std::fmt::{Debug, Display};
+
+use crate::{Formula, Item};
+
+/// Receives the steps of [`crate::Solver::Graph`], every method does nothing by default.
+pub trait Trace: Debug {
+    /// `formula` is selected for `item`, and `used` formulas outside its loop consume it
+    fn visit(&self, item: &Item, formula: &Formula, used: u32) {
+        let _ = (item, formula, used);
+    }
+
+    /// `formula` runs at `crafts` per minute, summed over everything consuming its outputs
+    fn crafts(&self, formula: &Formula, crafts: &dyn Display) {
+        let _ = (formula, crafts);
+    }
+}
+
+/// Prints every step to stderr.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Stderr;
+
+impl Trace for Stderr {
+    fn visit(&self, item: &Item, formula: &Formula, used: u32) {
+        eprintln!("visit {item}: {formula} used={used}");
+    }
+
+    fn crafts(&self, formula: &Formula, crafts: &dyn Display) {
+        eprintln!("crafts {formula}: {crafts}/min");
+    }
+}
@@ -0,0 +1,42 @@
+use std::{cell::RefCell, rc::Rc};
+
+use super::*;
+use crate::{calculate, FormulaList, Options};
+
+#[derive(Debug, Default)]
+struct Steps(RefCell<Vec<String>>);
+
+impl Trace for Steps {
+    fn visit(&self, item: &Item, _: &Formula, used: u32) {
+        self.0.borrow_mut().push(format!("{item} {used}"));
+    }
+
+    fn crafts(&self, formula: &Formula, crafts: &dyn Display) {
+        let item = formula.target().item();
+        self.0.borrow_mut().push(format!("{item} {crafts}"));
+    }
+}
+
+#[test]
+fn test_trace() {
+    let input = "铁矿石 1s
+铁锭 = 铁矿石 2s
+铁棒 = 铁锭 4s
+螺丝 = 铁锭 + 铁棒 6s";
+    let formula_list = FormulaList::from_reader(input.as_bytes()).unwrap();
+    let target = formula_list.get_item("螺丝").unwrap();
+    let steps = Rc::new(Steps::default());
+    let options = Options {
+        trace: Some(steps.clone()),
+        ..Default::default()
+    };
+    calculate(&formula_list, &[], target, 10, &options).unwrap();
+
+    let mut visits = steps.0.borrow()[..4].to_vec();
+    visits.sort();
+    assert_eq!(visits, ["螺丝 0", "铁棒 1", "铁矿石 1", "铁锭 2"]);
+    assert_eq!(
+        &steps.0.borrow()[4..],
+        ["螺丝 10", "铁棒 10", "铁锭 20", "铁矿石 20"]
+    );
+}